@@ -0,0 +1,45 @@
+//! Parser throughput benchmarks.
+//!
+//! Run with `cargo bench`. Any other invocation (e.g. `cargo test --benches`)
+//! runs each benchmark once, so the harness doubles as a smoke test.
+
+extern crate graphql;
+
+use graphql::language::lexer::Source;
+use graphql::language::parser::{Parser, ParseOptions};
+
+use std::env;
+use std::time::Instant;
+
+const KITCHEN_SINK: &str = include_str!("../tests/data/kitchen-sink.graphql");
+const INTROSPECTION: &str = include_str!("../tests/data/introspection.graphql");
+// Directive definitions with descriptions, arguments and defaults. They're
+// the only type system definitions the parser reads so far, so this stands
+// in for SDL until type definitions are parsed.
+const DIRECTIVES: &str = include_str!("../tests/data/schema-directives.graphql");
+
+fn bench(name: &str, body: &str, iterations: u32) {
+    let source = Source::new(body);
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let document = Parser::parse(source.clone(), ParseOptions::new());
+        assert!(document.is_ok(), "{} failed to parse", name);
+    }
+    let elapsed = start.elapsed();
+    let per_iter = elapsed / iterations;
+    let mb_per_sec = (body.len() as f64 * f64::from(iterations)) / elapsed.as_secs_f64() / 1_000_000.0;
+    println!("{:<21} {:>8} bytes {:>12?}/iter {:>10.2} MB/s", name, body.len(), per_iter, mb_per_sec);
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; anything else is a smoke run.
+    let iterations = if env::args().any(|arg| arg == "--bench") { 2_000 } else { 1 };
+
+    // Fifty copies of the introspection query stand in for a large document.
+    let large = INTROSPECTION.repeat(50);
+
+    bench("kitchen-sink", KITCHEN_SINK, iterations);
+    bench("introspection", INTROSPECTION, iterations);
+    bench("directive-definitions", DIRECTIVES, iterations);
+    bench("large-document", &large, iterations / 20 + 1);
+}
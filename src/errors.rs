@@ -0,0 +1,100 @@
+use crate::language::errors::{ParseError, OperationError};
+use crate::language::lexer::SourceLocation;
+use crate::value::{Value, Map};
+
+use std::fmt;
+
+/// A step on the way from the root of a response to a field.
+#[derive(PartialEq, Debug, Clone)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize)
+}
+
+/// An error reported in a response.
+#[derive(PartialEq, Debug)]
+pub struct GraphQLError {
+    pub message: String,
+    /// Where in the query the error happened.
+    pub locations: Vec<SourceLocation>,
+    /// The response key and list indices leading to the failed field.
+    pub path: Vec<PathSegment>
+}
+
+impl GraphQLError {
+    pub fn new(message: String) -> GraphQLError {
+        GraphQLError {
+            message,
+            locations: vec![],
+            path: vec![]
+        }
+    }
+
+    /// The error in its response shape, e.g.
+    /// `{"message": "...", "locations": [{"line": 1, "column": 3}], "path": ["hero", 0]}`.
+    pub fn to_value(&self) -> Value {
+        let mut fields = Map::new();
+        fields.insert("message".to_owned(), Value::String(self.message.clone()));
+        if !self.locations.is_empty() {
+            fields.insert("locations".to_owned(), Value::List(self.locations.iter().map(|location| {
+                let mut fields = Map::new();
+                fields.insert("line".to_owned(), Value::Int(location.line as i64));
+                fields.insert("column".to_owned(), Value::Int(location.column as i64));
+                Value::Object(fields)
+            }).collect()));
+        }
+        if !self.path.is_empty() {
+            fields.insert("path".to_owned(), Value::List(self.path.iter().map(|segment| match *segment {
+                PathSegment::Key(ref key) => Value::String(key.clone()),
+                PathSegment::Index(index) => Value::Int(index as i64)
+            }).collect()));
+        }
+        Value::Object(fields)
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<ParseError> for GraphQLError {
+    fn from(error: ParseError) -> GraphQLError {
+        let mut converted = GraphQLError::new(error.to_string());
+        converted.locations.extend(error.location);
+        converted
+    }
+}
+
+impl From<OperationError> for GraphQLError {
+    fn from(error: OperationError) -> GraphQLError {
+        GraphQLError::new(error.description)
+    }
+}
+
+/// The error a resolver returns when it can't produce a value.
+#[derive(PartialEq, Debug)]
+pub struct FieldError {
+    pub message: String
+}
+
+impl FieldError {
+    pub fn new(message: String) -> FieldError {
+        FieldError {
+            message
+        }
+    }
+}
+
+impl From<&str> for FieldError {
+    fn from(message: &str) -> FieldError {
+        FieldError::new(message.to_owned())
+    }
+}
+
+impl From<String> for FieldError {
+    fn from(message: String) -> FieldError {
+        FieldError::new(message)
+    }
+}
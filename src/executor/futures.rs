@@ -0,0 +1,166 @@
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Runs `future` to completion on the current thread, parking it while the
+/// future waits. This is enough for resolvers that don't need a particular
+/// runtime; ones that do should be awaited from inside that runtime.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park()
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Waits for all of `futures` at once, giving their outputs in order, or
+/// the first error. After an error, futures that were never polled are
+/// dropped without starting, but the ones already running are still
+/// polled until they finish, as graphql-js lets pending promises settle.
+pub fn try_join_all<'a, T, E>(futures: Vec<BoxFuture<'a, Result<T, E>>>) -> TryJoinAll<'a, T, E> {
+    let count = futures.len();
+    let ready = Arc::new(ReadyQueue {
+        slots: Mutex::new(ReadySlots { order: (0..count).collect(), queued: vec![true; count] }),
+        waker: Mutex::new(None)
+    });
+    TryJoinAll {
+        slots: futures.into_iter().map(Slot::Pending).collect(),
+        wakers: (0..count).map(|index| Waker::from(Arc::new(SlotWaker { index, ready: ready.clone() }))).collect(),
+        ready,
+        running: count,
+        error: None
+    }
+}
+
+/// Each future gets a waker of its own, so a poll only goes to the futures
+/// that woke since the last one, rather than to every future still running.
+pub struct TryJoinAll<'a, T, E> {
+    slots: Vec<Slot<'a, T, E>>,
+    wakers: Vec<Waker>,
+    ready: Arc<ReadyQueue>,
+    /// How many slots have yet to finish or be dropped.
+    running: usize,
+    error: Option<E>
+}
+
+enum Slot<'a, T, E> {
+    Pending(BoxFuture<'a, Result<T, E>>),
+    Running(BoxFuture<'a, Result<T, E>>),
+    Done(Option<T>)
+}
+
+/// The slots woken since they were last polled, and the task waiting on
+/// the join.
+struct ReadyQueue {
+    slots: Mutex<ReadySlots>,
+    waker: Mutex<Option<Waker>>
+}
+
+struct ReadySlots {
+    /// Indices in the order they woke.
+    order: Vec<usize>,
+    /// Whether each slot is already in `order`.
+    queued: Vec<bool>
+}
+
+struct SlotWaker {
+    index: usize,
+    ready: Arc<ReadyQueue>
+}
+
+impl Wake for SlotWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        {
+            let mut slots = self.ready.slots.lock().unwrap();
+            if mem::replace(&mut slots.queued[self.index], true) {
+                return;
+            }
+            slots.order.push(self.index);
+        }
+        if let Some(ref waker) = *self.ready.waker.lock().unwrap() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+// The outputs are never pinned, only the boxed futures, which stay put.
+impl<'a, T, E> Unpin for TryJoinAll<'a, T, E> {}
+
+impl<'a, T, E> Future for TryJoinAll<'a, T, E> {
+    type Output = Result<Vec<T>, E>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        let join = self.get_mut();
+        {
+            let mut waker = join.ready.waker.lock().unwrap();
+            if !waker.as_ref().is_some_and(|waker| waker.will_wake(context.waker())) {
+                *waker = Some(context.waker().clone());
+            }
+        }
+        // Slots woken while this batch is polled wait for the next poll.
+        let batch = {
+            let mut slots = join.ready.slots.lock().unwrap();
+            let batch = mem::take(&mut slots.order);
+            for &index in &batch {
+                slots.queued[index] = false;
+            }
+            batch
+        };
+        for index in batch {
+            let slot = &mut join.slots[index];
+            let mut future = match mem::replace(slot, Slot::Done(None)) {
+                Slot::Pending(_) if join.error.is_some() => {
+                    join.running -= 1;
+                    continue;
+                },
+                Slot::Pending(future) | Slot::Running(future) => future,
+                done => {
+                    *slot = done;
+                    continue;
+                }
+            };
+            match future.as_mut().poll(&mut Context::from_waker(&join.wakers[index])) {
+                Poll::Ready(Ok(output)) => {
+                    *slot = Slot::Done(Some(output));
+                    join.running -= 1;
+                },
+                Poll::Ready(Err(error)) => {
+                    join.error.get_or_insert(error);
+                    join.running -= 1;
+                },
+                Poll::Pending => *slot = Slot::Running(future)
+            }
+        }
+        if join.running > 0 {
+            return Poll::Pending;
+        }
+        if let Some(error) = join.error.take() {
+            return Poll::Ready(Err(error));
+        }
+        Poll::Ready(Ok(join.slots.iter_mut().map(|slot| match *slot {
+            Slot::Done(ref mut output) => output.take().expect("polled after completion"),
+            _ => unreachable!()
+        }).collect()))
+    }
+}
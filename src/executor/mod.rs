@@ -0,0 +1,420 @@
+pub mod values;
+mod futures;
+
+use crate::errors::{GraphQLError, PathSegment};
+use crate::language::ast::{Document, Definition, Directive, OperationType, SelectionSet, Selection, Type as AstType};
+use crate::language::operation::{get_operation, get_fragments};
+use crate::request::{Request, Response, Context, Variables};
+use crate::types::definition::{Schema, Type, TypeRef};
+use crate::value::{Value, Map};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+pub use self::futures::{BoxFuture, block_on};
+
+use self::futures::try_join_all;
+use self::values::{Arguments, coerce_variable_values, coerce_argument_values, value_from_ast};
+
+/// Fields to execute by response key, in query order.
+type GroupedFields<'a> = Vec<(String, Vec<&'a Selection>)>;
+
+/// A non-null position couldn't get a value. The error has already been
+/// recorded; the parent has to become null in turn, or fail itself if it's
+/// non-null too.
+struct Failed;
+
+type Completion<'b> = BoxFuture<'b, Result<Value, Failed>>;
+
+/// What a resolver gets to work with.
+pub struct ResolveInfo<'a> {
+    /// The value of the object the field belongs to.
+    pub parent: &'a Value,
+    pub context: &'a Context,
+    pub variables: &'a Variables,
+    pub field_name: &'a str,
+    /// The field's arguments, coerced to their definitions.
+    pub arguments: &'a Arguments,
+    /// Where in the response the field's value goes.
+    pub path: &'a [PathSegment]
+}
+
+pub struct Executor<'a> {
+    schema: &'a Schema,
+    fragments: HashMap<&'a str, &'a Definition>,
+    request: &'a Request,
+    /// The request's variables, coerced to the operation's definitions.
+    variables: Variables,
+    /// Shared by fields that are executing at the same time.
+    errors: Mutex<Vec<GraphQLError>>
+}
+
+impl<'a> Executor<'a> {
+    /// Executes the operation `request` asks for from `document`, which
+    /// should have been parsed from `request.query`, waiting for any
+    /// asynchronous resolvers on the current thread.
+    pub fn execute(schema: &Schema, document: &Document, request: &Request) -> Response {
+        block_on(Executor::execute_async(schema, document, request))
+    }
+
+    /// Like `execute`, but leaves waiting for resolvers to the caller, so it
+    /// can be awaited from any runtime.
+    pub async fn execute_async(schema: &'a Schema, document: &'a Document, request: &'a Request) -> Response {
+        let operation = match get_operation(document, request.operation_name.as_ref().map(|name| &name[..])) {
+            Ok(operation) => operation,
+            Err(error) => return Response::from_error(error.into())
+        };
+        let fragments = match get_fragments(document, operation) {
+            Ok(fragments) => fragments,
+            Err(error) => return Response::from_error(error.into())
+        };
+
+        let (operation_type, variable_definitions, selection_set) = match *operation {
+            Definition::Operation { operation, ref variable_definitions, ref selection_set, .. } => {
+                (operation, variable_definitions.as_ref().map_or(&[][..], |definitions| &definitions[..]), selection_set)
+            },
+            _ => unreachable!()
+        };
+        let root = match operation_type {
+            OperationType::Query => Some(schema.query_type()),
+            OperationType::Mutation => schema.mutation_type(),
+            OperationType::Subscription => None
+        };
+        let root = match root {
+            Some(root) => root,
+            None => return Response::from_error(GraphQLError::new(format!("Schema is not configured for {}s.", operation_type)))
+        };
+        let variables = match coerce_variable_values(schema, variable_definitions, &request.variables) {
+            Ok(variables) => variables,
+            Err(errors) => return Response { data: None, errors, extensions: None }
+        };
+
+        let executor = Executor {
+            schema,
+            fragments: fragments.into_iter().filter_map(|fragment| match *fragment {
+                Definition::Fragment { ref name, .. } => Some((&name.value[..], fragment)),
+                _ => None
+            }).collect(),
+            request,
+            variables,
+            errors: Mutex::new(vec![])
+        };
+        let grouped_fields = executor.group_fields(root, &[selection_set]);
+        let data = match operation_type {
+            OperationType::Mutation => executor.execute_fields_serially(root, &request.root_value, grouped_fields).await,
+            _ => executor.execute_fields(root, &request.root_value, grouped_fields, &[]).await
+        };
+
+        Response {
+            data: Some(data.unwrap_or(Value::Null)),
+            errors: executor.errors.into_inner().unwrap(),
+            extensions: None
+        }
+    }
+
+    /// Collects the fields of one or more selection sets for `object_type`.
+    /// Several selection sets are merged, as happens when a field is
+    /// selected more than once with different sub-selections.
+    fn group_fields(&self, object_type: &Type, selection_sets: &[&'a SelectionSet]) -> GroupedFields<'a> {
+        let mut grouped_fields = vec![];
+        let mut visited_fragments = HashSet::new();
+        for selection_set in selection_sets {
+            self.collect_fields(object_type, selection_set, &mut grouped_fields, &mut visited_fragments);
+        }
+        grouped_fields
+    }
+
+    /// Executes `grouped_fields` against `parent` all at once, so fields
+    /// with asynchronous resolvers wait side by side. A failure that nulls
+    /// the whole object still waits for the fields already running, so
+    /// their errors are reported, but fields not yet started never are.
+    async fn execute_fields(&self, object_type: &Type, parent: &Value, grouped_fields: GroupedFields<'a>, path: &[PathSegment]) -> Result<Value, Failed> {
+        let mut keys = Vec::with_capacity(grouped_fields.len());
+        let mut fields = Vec::with_capacity(grouped_fields.len());
+        for (key, selections) in grouped_fields {
+            let mut field_path = path.to_vec();
+            field_path.push(PathSegment::Key(key.clone()));
+            fields.push(self.execute_field(object_type, parent, selections, field_path));
+            keys.push(key);
+        }
+        let values = try_join_all(fields).await?;
+        Ok(Value::Object(keys.into_iter().zip(values).collect()))
+    }
+
+    /// Executes the root fields of a mutation one at a time in query order,
+    /// so each field's side effects happen before the next one starts. A
+    /// failure that nulls the whole result stops the rest from running.
+    async fn execute_fields_serially(&self, object_type: &Type, parent: &Value, grouped_fields: GroupedFields<'a>) -> Result<Value, Failed> {
+        let mut result = Map::with_capacity(grouped_fields.len());
+        for (key, selections) in grouped_fields {
+            let path = vec![PathSegment::Key(key.clone())];
+            let value = self.execute_field(object_type, parent, selections, path).await?;
+            result.insert(key, value);
+        }
+        Ok(Value::Object(result))
+    }
+
+    /// Groups the fields to execute by response key, in the order they first
+    /// appear, expanding fragments that apply to `object_type` and leaving
+    /// out selections excluded by `@skip` or `@include`.
+    fn collect_fields(&self, object_type: &Type, selection_set: &'a SelectionSet, grouped_fields: &mut GroupedFields<'a>,
+                      visited_fragments: &mut HashSet<&'a str>) {
+        for selection in &selection_set.selections {
+            match *selection {
+                Selection::Field { ref alias, ref name, ref directives, .. } => {
+                    if !self.should_include(directives) {
+                        continue;
+                    }
+                    let key = &alias.as_ref().unwrap_or(name).value;
+                    match grouped_fields.iter_mut().find(|(k, _)| k == key) {
+                        Some((_, fields)) => fields.push(selection),
+                        None => grouped_fields.push((key.clone(), vec![selection]))
+                    }
+                },
+                Selection::FragmentSpread { ref name, ref directives, .. } => {
+                    if !self.should_include(directives.as_ref().map_or(&[], |d| &d[..])) || !visited_fragments.insert(&name.value) {
+                        continue;
+                    }
+                    if let Some(Definition::Fragment { type_condition, selection_set, .. }) = self.fragments.get(&name.value[..]) {
+                        if self.applies(object_type, type_condition) {
+                            self.collect_fields(object_type, selection_set, grouped_fields, visited_fragments);
+                        }
+                    }
+                },
+                Selection::InlineFragment { ref type_condition, ref directives, ref selection_set, .. } => {
+                    if !self.should_include(directives.as_ref().map_or(&[], |d| &d[..])) {
+                        continue;
+                    }
+                    if type_condition.as_ref().is_none_or(|type_condition| self.applies(object_type, type_condition)) {
+                        self.collect_fields(object_type, selection_set, grouped_fields, visited_fragments);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies `@skip(if:)` and `@include(if:)`.
+    fn should_include(&self, directives: &[Directive]) -> bool {
+        let condition = |directive: &Directive| {
+            let argument = directive.arguments.iter().flatten().find(|argument| argument.name.value == "if")?;
+            let boolean = TypeRef::non_null(TypeRef::named("Boolean"));
+            match value_from_ast(self.schema, &argument.value, &boolean, Some(&self.variables)) {
+                Some(Value::Boolean(condition)) => Some(condition),
+                _ => None
+            }
+        };
+        directives.iter().all(|directive| match &directive.name.value[..] {
+            "skip" => condition(directive) != Some(true),
+            "include" => condition(directive) != Some(false),
+            _ => true
+        })
+    }
+
+    /// Resolves and completes the value for one response key. `fields` are
+    /// all the selections of that key; the first one names the field and
+    /// gives its arguments.
+    fn execute_field<'b>(&'b self, object_type: &'b Type, parent: &'b Value, fields: Vec<&'a Selection>,
+                         path: Vec<PathSegment>) -> Completion<'b> {
+        Box::pin(async move {
+            let selection = fields[0];
+            let (name, arguments) = match *selection {
+                Selection::Field { ref name, ref arguments, .. } => (&name.value[..], arguments),
+                _ => unreachable!()
+            };
+            if name == "__typename" {
+                return Ok(Value::String(object_type.name().to_owned()));
+            }
+            let field = match object_type.field(name) {
+                Some(field) => field,
+                None => {
+                    self.error(format!("Cannot query field \"{}\" on type \"{}\".", name, object_type.name()), selection, &path);
+                    return Ok(Value::Null);
+                }
+            };
+
+            let resolved = match coerce_argument_values(self.schema, field, arguments, &self.variables) {
+                Ok(arguments) => {
+                    let info = ResolveInfo {
+                        parent,
+                        context: &self.request.context,
+                        variables: &self.variables,
+                        field_name: name,
+                        arguments: &arguments,
+                        path: &path
+                    };
+                    field.resolve(&info).await
+                },
+                Err(error) => Err(error)
+            };
+            match resolved {
+                Ok(value) => self.complete_value(&field.field_type, object_type.name(), &fields, &path, value).await,
+                Err(error) => {
+                    self.error(error.message, selection, &path);
+                    match field.field_type {
+                        TypeRef::NonNull(_) => Err(Failed),
+                        _ => Ok(Value::Null)
+                    }
+                }
+            }
+        })
+    }
+
+    /// Shapes a resolved value into its response value according to the
+    /// field's type. A failure inside a nullable type makes it null; inside
+    /// a non-null type, or a null where one isn't allowed, it fails.
+    fn complete_value<'b>(&'b self, field_type: &'b TypeRef, parent_name: &'b str, fields: &'b [&'a Selection],
+                          path: &'b [PathSegment], value: Value) -> Completion<'b> {
+        Box::pin(async move {
+            match *field_type {
+                TypeRef::NonNull(ref inner) => {
+                    let completed = self.complete_nullable(inner, parent_name, fields, path, value).await?;
+                    if completed.is_null() {
+                        let message = format!("Cannot return null for non-nullable field {}.{}.", parent_name, field_name(fields[0]));
+                        self.error(message, fields[0], path);
+                        return Err(Failed);
+                    }
+                    Ok(completed)
+                },
+                _ => Ok(self.complete_nullable(field_type, parent_name, fields, path, value).await.unwrap_or(Value::Null))
+            }
+        })
+    }
+
+    /// Completes a value of a type that isn't itself non-null, failing if
+    /// anything inside it does. List items are completed all at once.
+    async fn complete_nullable(&self, field_type: &TypeRef, parent_name: &str, fields: &[&'a Selection],
+                               path: &[PathSegment], value: Value) -> Result<Value, Failed> {
+        let selection = fields[0];
+        if value.is_null() {
+            return Ok(Value::Null);
+        }
+        match *field_type {
+            TypeRef::NonNull(_) => unreachable!(),
+            TypeRef::List(ref inner) => {
+                let items = match value {
+                    Value::List(items) => items,
+                    _ => {
+                        let message = format!("Expected a list for field {}.{}.", parent_name, field_name(selection));
+                        self.error(message, selection, path);
+                        return Err(Failed);
+                    }
+                };
+                let paths: Vec<_> = (0..items.len()).map(|index| {
+                    let mut item_path = path.to_vec();
+                    item_path.push(PathSegment::Index(index));
+                    item_path
+                }).collect();
+                let completions = items.into_iter().zip(&paths).map(|(item, item_path)| {
+                    self.complete_value(inner, parent_name, fields, item_path, item)
+                }).collect();
+                Ok(Value::List(try_join_all(completions).await?))
+            },
+            TypeRef::Named(ref name) => {
+                let schema = self.schema;
+                let named_type = match schema.get_type(name) {
+                    Some(named_type) => named_type,
+                    None => {
+                        self.error(format!("Unknown type \"{}\".", name), selection, path);
+                        return Err(Failed);
+                    }
+                };
+                let serialized = match *named_type {
+                    Type::Scalar(ref scalar) => scalar.serialize(&value),
+                    Type::Enum(ref enum_type) => enum_type.serialize(&value),
+                    Type::Object(_) | Type::Interface(_) | Type::Union(_) => {
+                        let object_type = match self.resolve_object_type(named_type, &value, parent_name, selection, path) {
+                            Some(object_type) => object_type,
+                            None => return Err(Failed)
+                        };
+                        if let Type::Object(ref object) = *object_type {
+                            if object.is_type_of(&value, &self.request.context) == Some(false) {
+                                self.error(format!("Expected value of type \"{}\" but got: {}.", object.name, value.to_json()), selection, path);
+                                return Err(Failed);
+                            }
+                        }
+                        let selection_sets: Vec<_> = fields.iter().filter_map(|field| match **field {
+                            Selection::Field { ref selection_set, .. } => selection_set.as_ref(),
+                            _ => None
+                        }).collect();
+                        if selection_sets.is_empty() {
+                            self.error(format!("Field \"{}\" of type \"{}\" must have a selection of subfields.", field_name(selection), name), selection, path);
+                            return Err(Failed);
+                        }
+                        let grouped_fields = self.group_fields(object_type, &selection_sets);
+                        return self.execute_fields(object_type, &value, grouped_fields, path).await;
+                    },
+                    Type::InputObject(_) => {
+                        self.error(format!("Input object \"{}\" cannot be used as an output type.", name), selection, path);
+                        return Err(Failed);
+                    }
+                };
+                serialized.ok_or_else(|| {
+                    self.error(format!("{} cannot represent value: {}", name, value.to_json()), selection, path);
+                    Failed
+                })
+            }
+        }
+    }
+
+    /// The object type of `value`, which is of `named_type`. Interfaces and
+    /// unions use their `resolve_type`, or else the first possible type
+    /// whose `is_type_of` accepts the value. Reports an error if neither
+    /// gives a possible type.
+    fn resolve_object_type(&self, named_type: &'a Type, value: &Value, parent_name: &str, selection: &Selection,
+                           path: &[PathSegment]) -> Option<&'a Type> {
+        let schema = self.schema;
+        let context = &self.request.context;
+        let object_name = match *named_type {
+            Type::Object(_) => return Some(named_type),
+            _ => match named_type.resolve_type(value, context) {
+                Some(name) => name,
+                None => schema.possible_types(named_type).into_iter()
+                    .find(|object| object.is_type_of(value, context) == Some(true))
+                    .map(|object| object.name.clone())
+            }
+        };
+
+        let message = match object_name.as_ref().map(|name| (name, schema.get_type(name))) {
+            Some((_, Some(object_type @ Type::Object(object)))) if schema.is_possible_type(named_type, object) => return Some(object_type),
+            Some((name, Some(Type::Object(_)))) => format!("Runtime Object type \"{}\" is not a possible type for \"{}\".", name, named_type.name()),
+            Some((name, Some(_))) => format!("Abstract type \"{}\" must resolve to an Object type at runtime for field \"{}.{}\". Got \"{}\".",
+                                             named_type.name(), parent_name, field_name(selection), name),
+            Some((name, None)) => format!("Abstract type \"{}\" was resolved to a type \"{}\" that does not exist inside the schema.", named_type.name(), name),
+            None => format!(concat!("Abstract type \"{}\" must resolve to an Object type at runtime for field \"{}.{}\". ",
+                                    "Either the \"{}\" type should provide a \"resolve_type\" function or each possible type should provide an \"is_type_of\" function."),
+                            named_type.name(), parent_name, field_name(selection), named_type.name())
+        };
+        self.error(message, selection, path);
+        None
+    }
+
+    /// Whether a fragment with `type_condition` applies to `object_type`.
+    fn applies(&self, object_type: &Type, type_condition: &AstType) -> bool {
+        let condition = match *type_condition {
+            AstType::Named { ref name, .. } => self.schema.get_type(&name.value),
+            _ => None
+        };
+        match (condition, object_type) {
+            (Some(condition), Type::Object(object)) => self.schema.is_possible_type(condition, object),
+            _ => false
+        }
+    }
+
+    fn error(&self, message: String, selection: &Selection, path: &[PathSegment]) {
+        let mut error = GraphQLError::new(message);
+        if let Selection::Field { loc: Some(ref loc), .. } = *selection {
+            if let Some(ref source) = loc.source {
+                error.locations.push(source.location(loc.start));
+            }
+        }
+        error.path = path.to_vec();
+        self.errors.lock().unwrap().push(error);
+    }
+}
+
+fn field_name(selection: &Selection) -> &str {
+    match *selection {
+        Selection::Field { ref name, .. } => &name.value,
+        _ => ""
+    }
+}
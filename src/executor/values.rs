@@ -0,0 +1,301 @@
+use crate::errors::{GraphQLError, FieldError};
+use crate::language::ast::{self, Argument, VariableDefinition, Location};
+use crate::language::printer::print_value;
+use crate::request::Variables;
+use crate::types::definition::{Schema, Type, TypeRef, Field};
+use crate::value::{Value, Map, FromValue};
+
+use std::collections::HashMap;
+
+/// Checks the request's variable values against the operation's variable
+/// definitions, filling in defaults. Every invalid variable is reported.
+pub fn coerce_variable_values(schema: &Schema, definitions: &[VariableDefinition], inputs: &Variables) -> Result<Variables, Vec<GraphQLError>> {
+    let mut coerced = HashMap::new();
+    let mut errors = vec![];
+
+    for definition in definitions {
+        let name = match definition.variable {
+            ast::Value::VariableValue { ref name, .. } => &name.value,
+            _ => continue
+        };
+        let var_type = TypeRef::from(&definition.var_type);
+        if !schema.get_type(var_type.name()).is_some_and(Type::is_input_type) {
+            let message = format!("Variable \"${}\" expected value of type \"{}\" which cannot be used as an input type.", name, var_type);
+            errors.push(located(message, &definition.loc));
+            continue;
+        }
+
+        let value = match inputs.get(name) {
+            Some(value) => value,
+            None => {
+                if let Some(ref default_value) = definition.default_value {
+                    match value_from_ast(schema, default_value, &var_type, None) {
+                        Some(value) => { coerced.insert(name.clone(), value); },
+                        None => {
+                            let message = format!("Variable \"${}\" has an invalid default value.", name);
+                            errors.push(located(message, &definition.loc));
+                        }
+                    }
+                } else if let TypeRef::NonNull(_) = var_type {
+                    let message = format!("Variable \"${}\" of required type \"{}\" was not provided.", name, var_type);
+                    errors.push(located(message, &definition.loc));
+                }
+                continue;
+            }
+        };
+
+        if value.is_null() {
+            if let TypeRef::NonNull(_) = var_type {
+                let message = format!("Variable \"${}\" of non-null type \"{}\" must not be null.", name, var_type);
+                errors.push(located(message, &definition.loc));
+                continue;
+            }
+        }
+
+        let mut failures = vec![];
+        let result = coerce_input_value(schema, value, &var_type, &mut name.clone(), &mut failures);
+        if failures.is_empty() {
+            coerced.insert(name.clone(), result);
+        }
+        for (path, invalid, reason) in failures {
+            let message = if path == *name {
+                format!("Variable \"${}\" got invalid value {}; {}", name, invalid.to_json(), reason)
+            } else {
+                format!("Variable \"${}\" got invalid value {} at \"{}\"; {}", name, invalid.to_json(), path, reason)
+            };
+            errors.push(located(message, &definition.loc));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(coerced)
+    } else {
+        Err(errors)
+    }
+}
+
+/// A field's coerced arguments. Arguments that were left out and have no
+/// default are missing, which is different from being given as null.
+#[derive(PartialEq, Debug, Default)]
+pub struct Arguments {
+    values: Vec<(String, Value)>
+}
+
+impl Arguments {
+    pub fn new() -> Arguments {
+        Arguments { values: vec![] }
+    }
+
+    /// Whether the argument was given or has a default, even if it's null.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.iter().any(|(key, _)| key == name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|(key, _)| key == name).map(|(_, value)| value)
+    }
+
+    /// The argument converted to `T`. Missing arguments read as null, so
+    /// ask for an `Option` when the argument is nullable.
+    pub fn get<T: FromValue>(&self, name: &str) -> Result<T, FieldError> {
+        let value = self.value(name).unwrap_or(&Value::Null);
+        T::from_value(value).ok_or_else(|| {
+            FieldError::new(format!("Argument \"{}\" has an unexpected value {}.", name, value.to_json()))
+        })
+    }
+}
+
+/// Coerces the arguments given to a field in a query against the field's
+/// argument definitions.
+pub fn coerce_argument_values(schema: &Schema, field: &Field, arguments: &[Argument], variables: &Variables) -> Result<Arguments, FieldError> {
+    let mut coerced = Arguments::new();
+    for definition in field.arguments() {
+        let name = &definition.name;
+        let argument = arguments.iter().find(|argument| argument.name.value == *name);
+        let (has_value, is_null) = match argument.map(|argument| &argument.value) {
+            Some(ast::Value::VariableValue { name: variable, .. }) => {
+                match variables.get(&variable.value) {
+                    Some(value) => (true, value.is_null()),
+                    None => (false, true)
+                }
+            },
+            Some(ast::Value::NullValue { .. }) => (true, true),
+            Some(_) => (true, false),
+            None => (false, true)
+        };
+
+        if !has_value {
+            if let Some(ref default_value) = definition.default_value {
+                coerced.values.push((name.clone(), default_value.clone()));
+                continue;
+            }
+        }
+        if let TypeRef::NonNull(_) = definition.value_type {
+            if !has_value {
+                return Err(FieldError::new(format!("Argument \"{}\" of required type \"{}\" was not provided.", name, definition.value_type)));
+            }
+            if is_null {
+                return Err(FieldError::new(format!("Argument \"{}\" of non-null type \"{}\" must not be null.", name, definition.value_type)));
+            }
+        }
+        if let (true, Some(argument)) = (has_value, argument) {
+            match value_from_ast(schema, &argument.value, &definition.value_type, Some(variables)) {
+                Some(value) => coerced.values.push((name.clone(), value)),
+                None => return Err(FieldError::new(format!("Argument \"{}\" has invalid value {}.", name, print_value(&argument.value))))
+            }
+        }
+    }
+    Ok(coerced)
+}
+
+/// Coerces a runtime input value, such as a variable's, to `input_type`.
+/// Each failure is recorded with the path to the offending value, the value
+/// itself and the reason, and the failing part of the result is null.
+fn coerce_input_value(schema: &Schema, value: &Value, input_type: &TypeRef, path: &mut String,
+                      failures: &mut Vec<(String, Value, String)>) -> Value {
+    match *input_type {
+        TypeRef::NonNull(ref inner) => {
+            if value.is_null() {
+                return fail(failures, path, value, format!("Expected non-nullable type \"{}\" not to be null.", input_type));
+            }
+            coerce_input_value(schema, value, inner, path, failures)
+        },
+        _ if value.is_null() => Value::Null,
+        TypeRef::List(ref inner) => match *value {
+            Value::List(ref items) => {
+                let mut coerced = Vec::with_capacity(items.len());
+                for (index, item) in items.iter().enumerate() {
+                    let length = path.len();
+                    path.push_str(&format!("[{}]", index));
+                    coerced.push(coerce_input_value(schema, item, inner, path, failures));
+                    path.truncate(length);
+                }
+                Value::List(coerced)
+            },
+            // A single item is accepted where a list is expected.
+            _ => Value::List(vec![coerce_input_value(schema, value, inner, path, failures)])
+        },
+        TypeRef::Named(ref name) => match schema.get_type(name) {
+            Some(Type::Scalar(scalar)) => scalar.parse_value(value).unwrap_or_else(|| {
+                fail(failures, path, value, format!("Expected type \"{}\".", name))
+            }),
+            Some(Type::Enum(enum_type)) => enum_type.parse_value(value).unwrap_or_else(|| {
+                fail(failures, path, value, format!("Expected type \"{}\".", name))
+            }),
+            Some(Type::InputObject(input_object)) => {
+                let fields = match *value {
+                    Value::Object(ref fields) => fields,
+                    _ => return fail(failures, path, value, format!("Expected type \"{}\" to be an object.", name))
+                };
+
+                let mut coerced = Map::new();
+                for field in input_object.fields() {
+                    match value.get(&field.name) {
+                        Some(field_value) => {
+                            let length = path.len();
+                            path.push('.');
+                            path.push_str(&field.name);
+                            let field_value = coerce_input_value(schema, field_value, &field.value_type, path, failures);
+                            path.truncate(length);
+                            coerced.insert(field.name.clone(), field_value);
+                        },
+                        None => {
+                            if let Some(ref default_value) = field.default_value {
+                                coerced.insert(field.name.clone(), default_value.clone());
+                            } else if let TypeRef::NonNull(_) = field.value_type {
+                                let reason = format!("Field \"{}\" of required type \"{}\" was not provided.", field.name, field.value_type);
+                                fail(failures, path, value, reason);
+                            }
+                        }
+                    }
+                }
+                for (key, _) in fields {
+                    if !input_object.fields().iter().any(|field| field.name == *key) {
+                        fail(failures, path, value, format!("Field \"{}\" is not defined by type \"{}\".", key, name));
+                    }
+                }
+                Value::Object(coerced)
+            },
+            _ => fail(failures, path, value, format!("Expected an input type, found \"{}\".", name))
+        }
+    }
+}
+
+fn fail(failures: &mut Vec<(String, Value, String)>, path: &str, value: &Value, reason: String) -> Value {
+    failures.push((path.to_owned(), value.clone(), reason));
+    Value::Null
+}
+
+/// Converts a literal from a query into a value of `input_type`, taking
+/// variable references from `variables`. Returns `None` if the literal
+/// isn't valid for the type, or if it uses variables and none are given.
+pub fn value_from_ast(schema: &Schema, value: &ast::Value, input_type: &TypeRef, variables: Option<&Variables>) -> Option<Value> {
+    if let ast::Value::VariableValue { ref name, .. } = *value {
+        let variable = variables?.get(&name.value).cloned().unwrap_or(Value::Null);
+        return match *input_type {
+            TypeRef::NonNull(_) if variable.is_null() => None,
+            _ => Some(variable)
+        };
+    }
+
+    match *input_type {
+        TypeRef::NonNull(ref inner) => match *value {
+            ast::Value::NullValue { .. } => None,
+            _ => value_from_ast(schema, value, inner, variables)
+        },
+        _ if matches!(*value, ast::Value::NullValue { .. }) => Some(Value::Null),
+        TypeRef::List(ref inner) => match *value {
+            ast::Value::ArrayValue { ref values, .. } => {
+                values.iter().map(|item| value_from_ast(schema, item, inner, variables)).collect::<Option<_>>().map(Value::List)
+            },
+            _ => value_from_ast(schema, value, inner, variables).map(|item| Value::List(vec![item]))
+        },
+        TypeRef::Named(ref name) => match schema.get_type(name)? {
+            Type::Scalar(scalar) => scalar.parse_literal(value),
+            Type::Enum(enum_type) => match *value {
+                ast::Value::EnumValue { ref value, .. } => enum_type.parse_value(&Value::Enum(value.clone())),
+                _ => None
+            },
+            Type::InputObject(input_object) => {
+                let fields = match *value {
+                    ast::Value::ObjectValue { ref fields, .. } => fields,
+                    _ => return None
+                };
+                if fields.iter().any(|field| !input_object.fields().iter().any(|f| f.name == field.name.value)) {
+                    return None;
+                }
+
+                let mut coerced = Map::new();
+                for field in input_object.fields() {
+                    // A field set to a variable that wasn't given counts as left out.
+                    let given = fields.iter().find(|f| f.name.value == field.name).filter(|f| match f.value {
+                        ast::Value::VariableValue { ref name, .. } => variables.is_some_and(|variables| variables.contains_key(&name.value)),
+                        _ => true
+                    });
+                    match given {
+                        Some(f) => { coerced.insert(field.name.clone(), value_from_ast(schema, &f.value, &field.value_type, variables)?); },
+                        None => {
+                            if let Some(ref default_value) = field.default_value {
+                                coerced.insert(field.name.clone(), default_value.clone());
+                            } else if let TypeRef::NonNull(_) = field.value_type {
+                                return None;
+                            }
+                        }
+                    }
+                }
+                Some(Value::Object(coerced))
+            },
+            Type::Object(_) | Type::Interface(_) | Type::Union(_) => None
+        }
+    }
+}
+
+fn located(message: String, loc: &Option<Location>) -> GraphQLError {
+    let mut error = GraphQLError::new(message);
+    if let Some(ref loc) = *loc {
+        if let Some(ref source) = loc.source {
+            error.locations.push(source.location(loc.start));
+        }
+    }
+    error
+}
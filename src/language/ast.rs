@@ -1,8 +1,7 @@
-use language::kinds::Kinds;
-use language::lexer::Source;
+use crate::language::kinds::Kinds;
+use crate::language::lexer::Source;
 
 use std::fmt;
-use std::io::Write;
 
 #[derive(PartialEq)]
 pub struct Document {
@@ -25,7 +24,7 @@ impl fmt::Debug for Document {
 pub enum Definition {
     Operation {
         kind: Kinds,
-        operation: String,
+        operation: OperationType,
         name: Option<Name>,
         variable_definitions: Option<Vec<VariableDefinition>>,
         directives: Vec<Directive>,
@@ -35,10 +34,38 @@ pub enum Definition {
     Fragment {
         kind: Kinds,
         name: Name,
+        /// Only set when parsed with experimental fragment variables enabled.
+        variable_definitions: Option<Vec<VariableDefinition>>,
         type_condition: Type,
         directives: Option<Vec<Directive>>,
         selection_set: SelectionSet,
         loc: Option<Location>
+    },
+    Directive {
+        kind: Kinds,
+        description: Option<Value>,
+        name: Name,
+        arguments: Vec<InputValueDefinition>,
+        repeatable: bool,
+        locations: Vec<Name>,
+        loc: Option<Location>
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum OperationType {
+    Query,
+    Mutation,
+    Subscription
+}
+
+impl fmt::Display for OperationType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OperationType::Query        => write!(f, "query"),
+            OperationType::Mutation     => write!(f, "mutation"),
+            OperationType::Subscription => write!(f, "subscription")
+        }
     }
 }
 
@@ -48,6 +75,18 @@ pub struct VariableDefinition {
     pub variable: Value,
     pub var_type: Type,
     pub default_value: Option<Value>,
+    pub directives: Vec<Directive>,
+    pub loc: Option<Location>,
+}
+
+#[derive(PartialEq, Debug)]
+pub struct InputValueDefinition {
+    pub kind: Kinds,
+    pub description: Option<Value>,
+    pub name: Name,
+    pub value_type: Type,
+    pub default_value: Option<Value>,
+    pub directives: Vec<Directive>,
     pub loc: Option<Location>,
 }
 #[derive(PartialEq, Debug)]
@@ -83,7 +122,9 @@ pub enum Selection {
     },
     InlineFragment {
         kind: Kinds,
-        type_condition: Type,
+        /// `None` for fragments that only group selections, e.g. to put a
+        /// directive on them.
+        type_condition: Option<Type>,
         directives: Option<Vec<Directive>>,
         selection_set: SelectionSet,
         loc: Option<Location>,
@@ -128,8 +169,9 @@ pub struct Argument {
 pub enum Value {
     IntValue { kind: Kinds, value: String, loc: Option<Location> },
     FloatValue { kind: Kinds, value: String, loc: Option<Location> },
-    StringValue { kind: Kinds, value: String, loc: Option<Location> },
+    StringValue { kind: Kinds, value: String, block: bool, loc: Option<Location> },
     BooleanValue { kind: Kinds, value: bool, loc: Option<Location> },
+    NullValue { kind: Kinds, loc: Option<Location> },
     EnumValue { kind: Kinds, value: String, loc: Option<Location> },
     ArrayValue { kind: Kinds, values: Vec<Value>, loc: Option<Location> },
     ObjectValue { kind: Kinds, fields: Vec<ObjectField>, loc: Option<Location> },
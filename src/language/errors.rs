@@ -1,17 +1,71 @@
-#[macro_use]
 macro_rules! parse_error {
     ($($arg:tt)*) => (
         Err(ParseError::new(format!($($arg)*)))
     )
 }
 
+use crate::language::lexer::SourceLocation;
+
+use std::fmt;
+
+#[derive(PartialEq, Debug)]
 pub struct ParseError {
-    pub description: String
+    pub description: String,
+    /// The name of the `Source` that failed to parse, if it has one.
+    pub source_name: Option<String>,
+    /// Where in the source the parser gave up.
+    pub location: Option<SourceLocation>
 }
 
 impl ParseError {
     pub fn new(msg: String) -> ParseError {
         ParseError {
+            description: msg,
+            source_name: None,
+            location: None
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source_name {
+            Some(ref name) => write!(f, "{}: {}", name, self.description),
+            None           => write!(f, "{}", self.description)
+        }
+    }
+}
+
+impl From<LexError> for ParseError {
+    fn from(error: LexError) -> ParseError {
+        ParseError::new(error.description)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct LexError {
+    pub description: String,
+    pub position: usize
+}
+
+impl LexError {
+    pub fn new(msg: String, position: usize) -> LexError {
+        LexError {
+            description: msg,
+            position
+        }
+    }
+}
+
+/// Why an operation couldn't be picked out of a document.
+#[derive(PartialEq, Debug)]
+pub struct OperationError {
+    pub description: String
+}
+
+impl OperationError {
+    pub fn new(msg: String) -> OperationError {
+        OperationError {
             description: msg
         }
     }
@@ -0,0 +1,327 @@
+use crate::language::ast::{
+    Document,
+    Definition,
+    VariableDefinition,
+    InputValueDefinition,
+    SelectionSet,
+    Selection,
+    Argument,
+    Directive,
+    Name,
+    Type,
+    Value,
+    ObjectField
+};
+use crate::language::kinds::Kinds;
+
+use std::mem;
+
+/// Rewrites a `Document` by taking each node by value and returning its
+/// replacement.
+///
+/// Nodes that live in lists are folded into a `Vec`, so a fold can remove
+/// them (return nothing), replace them, or insert siblings next to them.
+/// Every default method folds the node's children and rebuilds it, keeping
+/// its original `Location`; nodes a fold creates itself should have no
+/// location.
+pub trait Fold {
+    fn fold_document(&mut self, node: Document) -> Document {
+        fold_document(self, node)
+    }
+
+    fn fold_definition(&mut self, node: Definition) -> Vec<Definition> {
+        fold_definition(self, node)
+    }
+
+    fn fold_variable_definition(&mut self, node: VariableDefinition) -> Vec<VariableDefinition> {
+        fold_variable_definition(self, node)
+    }
+
+    fn fold_input_value_definition(&mut self, node: InputValueDefinition) -> Vec<InputValueDefinition> {
+        fold_input_value_definition(self, node)
+    }
+
+    fn fold_selection_set(&mut self, node: SelectionSet) -> SelectionSet {
+        fold_selection_set(self, node)
+    }
+
+    fn fold_selection(&mut self, node: Selection) -> Vec<Selection> {
+        fold_selection(self, node)
+    }
+
+    fn fold_argument(&mut self, node: Argument) -> Vec<Argument> {
+        fold_argument(self, node)
+    }
+
+    fn fold_directive(&mut self, node: Directive) -> Vec<Directive> {
+        fold_directive(self, node)
+    }
+
+    fn fold_name(&mut self, node: Name) -> Name {
+        node
+    }
+
+    fn fold_type(&mut self, node: Type) -> Type {
+        fold_type(self, node)
+    }
+
+    fn fold_value(&mut self, node: Value) -> Value {
+        fold_value(self, node)
+    }
+
+    fn fold_object_field(&mut self, node: ObjectField) -> Vec<ObjectField> {
+        fold_object_field(self, node)
+    }
+}
+
+fn fold_list<F: Fold + ?Sized, T, M>(folder: &mut F, nodes: Vec<T>, mut method: M) -> Vec<T>
+    where M: FnMut(&mut F, T) -> Vec<T> {
+    let mut folded = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        folded.extend(method(folder, node));
+    }
+    folded
+}
+
+pub fn fold_document<F: Fold + ?Sized>(folder: &mut F, node: Document) -> Document {
+    Document {
+        kind: node.kind,
+        definitions: fold_list(folder, node.definitions, F::fold_definition),
+        loc: node.loc
+    }
+}
+
+pub fn fold_definition<F: Fold + ?Sized>(folder: &mut F, node: Definition) -> Vec<Definition> {
+    let definition = match node {
+        Definition::Operation { kind, operation, name, variable_definitions, directives, selection_set, loc } => {
+            Definition::Operation {
+                kind,
+                operation,
+                name: name.map(|n| folder.fold_name(n)),
+                variable_definitions: variable_definitions.map(|v| fold_list(folder, v, F::fold_variable_definition)),
+                directives: fold_list(folder, directives, F::fold_directive),
+                selection_set: folder.fold_selection_set(selection_set),
+                loc
+            }
+        },
+        Definition::Fragment { kind, name, variable_definitions, type_condition, directives, selection_set, loc } => {
+            Definition::Fragment {
+                kind,
+                name: folder.fold_name(name),
+                variable_definitions: variable_definitions.map(|v| fold_list(folder, v, F::fold_variable_definition)),
+                type_condition: folder.fold_type(type_condition),
+                directives: directives.map(|d| fold_list(folder, d, F::fold_directive)),
+                selection_set: folder.fold_selection_set(selection_set),
+                loc
+            }
+        },
+        Definition::Directive { kind, description, name, arguments, repeatable, locations, loc } => {
+            Definition::Directive {
+                kind,
+                description: description.map(|d| folder.fold_value(d)),
+                name: folder.fold_name(name),
+                arguments: fold_list(folder, arguments, F::fold_input_value_definition),
+                repeatable,
+                locations: locations.into_iter().map(|l| folder.fold_name(l)).collect(),
+                loc
+            }
+        }
+    };
+    vec![definition]
+}
+
+pub fn fold_variable_definition<F: Fold + ?Sized>(folder: &mut F, node: VariableDefinition) -> Vec<VariableDefinition> {
+    vec![VariableDefinition {
+        kind: node.kind,
+        variable: folder.fold_value(node.variable),
+        var_type: folder.fold_type(node.var_type),
+        default_value: node.default_value.map(|v| folder.fold_value(v)),
+        directives: fold_list(folder, node.directives, F::fold_directive),
+        loc: node.loc
+    }]
+}
+
+pub fn fold_input_value_definition<F: Fold + ?Sized>(folder: &mut F, node: InputValueDefinition) -> Vec<InputValueDefinition> {
+    vec![InputValueDefinition {
+        kind: node.kind,
+        description: node.description.map(|d| folder.fold_value(d)),
+        name: folder.fold_name(node.name),
+        value_type: folder.fold_type(node.value_type),
+        default_value: node.default_value.map(|v| folder.fold_value(v)),
+        directives: fold_list(folder, node.directives, F::fold_directive),
+        loc: node.loc
+    }]
+}
+
+pub fn fold_selection_set<F: Fold + ?Sized>(folder: &mut F, node: SelectionSet) -> SelectionSet {
+    SelectionSet {
+        kind: node.kind,
+        selections: fold_list(folder, node.selections, F::fold_selection),
+        loc: node.loc
+    }
+}
+
+pub fn fold_selection<F: Fold + ?Sized>(folder: &mut F, node: Selection) -> Vec<Selection> {
+    let selection = match node {
+        Selection::Field { kind, alias, name, arguments, directives, selection_set, loc } => {
+            Selection::Field {
+                kind,
+                alias: alias.map(|a| folder.fold_name(a)),
+                name: folder.fold_name(name),
+                arguments: fold_list(folder, arguments, F::fold_argument),
+                directives: fold_list(folder, directives, F::fold_directive),
+                selection_set: selection_set.map(|s| folder.fold_selection_set(s)),
+                loc
+            }
+        },
+        Selection::FragmentSpread { kind, name, directives, loc } => {
+            Selection::FragmentSpread {
+                kind,
+                name: folder.fold_name(name),
+                directives: directives.map(|d| fold_list(folder, d, F::fold_directive)),
+                loc
+            }
+        },
+        Selection::InlineFragment { kind, type_condition, directives, selection_set, loc } => {
+            Selection::InlineFragment {
+                kind,
+                type_condition: type_condition.map(|t| folder.fold_type(t)),
+                directives: directives.map(|d| fold_list(folder, d, F::fold_directive)),
+                selection_set: folder.fold_selection_set(selection_set),
+                loc
+            }
+        }
+    };
+    vec![selection]
+}
+
+pub fn fold_argument<F: Fold + ?Sized>(folder: &mut F, node: Argument) -> Vec<Argument> {
+    vec![Argument {
+        kind: node.kind,
+        name: folder.fold_name(node.name),
+        value: folder.fold_value(node.value),
+        loc: node.loc
+    }]
+}
+
+pub fn fold_directive<F: Fold + ?Sized>(folder: &mut F, node: Directive) -> Vec<Directive> {
+    vec![Directive {
+        kind: node.kind,
+        name: folder.fold_name(node.name),
+        arguments: node.arguments.map(|a| fold_list(folder, a, F::fold_argument)),
+        loc: node.loc
+    }]
+}
+
+pub fn fold_type<F: Fold + ?Sized>(folder: &mut F, node: Type) -> Type {
+    match node {
+        Type::Named { kind, name, loc } => Type::Named { kind, name: folder.fold_name(name), loc },
+        Type::List { kind, t_type, loc } => Type::List { kind, t_type: Box::new(folder.fold_type(*t_type)), loc },
+        Type::NonNull { kind, t_type, loc } => Type::NonNull { kind, t_type: Box::new(folder.fold_type(*t_type)), loc }
+    }
+}
+
+pub fn fold_value<F: Fold + ?Sized>(folder: &mut F, node: Value) -> Value {
+    match node {
+        Value::ArrayValue { kind, values, loc } => {
+            Value::ArrayValue { kind, values: values.into_iter().map(|v| folder.fold_value(v)).collect(), loc }
+        },
+        Value::ObjectValue { kind, fields, loc } => {
+            Value::ObjectValue { kind, fields: fold_list(folder, fields, F::fold_object_field), loc }
+        },
+        Value::VariableValue { kind, name, loc } => {
+            Value::VariableValue { kind, name: folder.fold_name(name), loc }
+        },
+        scalar => scalar
+    }
+}
+
+pub fn fold_object_field<F: Fold + ?Sized>(folder: &mut F, node: ObjectField) -> Vec<ObjectField> {
+    vec![ObjectField {
+        kind: node.kind,
+        name: folder.fold_name(node.name),
+        value: folder.fold_value(node.value),
+        loc: node.loc
+    }]
+}
+
+/// Adds a `__typename` field to every selection set below the root of each
+/// operation, unless it is already selected. Inline fragments directly on
+/// the root are part of the root.
+#[derive(Default)]
+pub struct AddTypename {
+    /// Whether the next selection set folded is an operation's root.
+    at_root: bool,
+    /// Whether the selections being folded belong to an operation's root.
+    in_root: bool
+}
+
+impl AddTypename {
+    pub fn new() -> AddTypename {
+        AddTypename { at_root: false, in_root: false }
+    }
+}
+
+impl Fold for AddTypename {
+    fn fold_definition(&mut self, node: Definition) -> Vec<Definition> {
+        if let Definition::Operation { .. } = node {
+            self.at_root = true;
+        }
+        fold_definition(self, node)
+    }
+
+    fn fold_selection_set(&mut self, node: SelectionSet) -> SelectionSet {
+        let at_root = self.at_root;
+        self.at_root = false;
+
+        let in_root = mem::replace(&mut self.in_root, at_root);
+        let mut node = fold_selection_set(self, node);
+        self.in_root = in_root;
+        let selected = node.selections.iter().any(|s| match *s {
+            Selection::Field { ref alias, ref name, .. } => alias.is_none() && name.value == "__typename",
+            _ => false
+        });
+        if !at_root && !selected {
+            node.selections.push(Selection::Field {
+                kind: Kinds::Field,
+                alias: None,
+                name: Name { kind: Kinds::Name, value: "__typename".to_owned(), loc: None },
+                arguments: vec![],
+                directives: vec![],
+                selection_set: None,
+                loc: None
+            });
+        }
+        node
+    }
+
+    fn fold_selection(&mut self, node: Selection) -> Vec<Selection> {
+        if let Selection::InlineFragment { .. } = node {
+            self.at_root = self.in_root;
+        }
+        fold_selection(self, node)
+    }
+}
+
+/// Removes every directive with one of the given names, e.g. `@client`.
+pub struct RemoveDirectives {
+    names: Vec<String>
+}
+
+impl RemoveDirectives {
+    pub fn new(names: &[&str]) -> RemoveDirectives {
+        RemoveDirectives {
+            names: names.iter().map(|n| n.to_string()).collect()
+        }
+    }
+}
+
+impl Fold for RemoveDirectives {
+    fn fold_directive(&mut self, node: Directive) -> Vec<Directive> {
+        if self.names.contains(&node.name.value) {
+            vec![]
+        } else {
+            fold_directive(self, node)
+        }
+    }
+}
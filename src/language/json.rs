@@ -0,0 +1,522 @@
+//! Converts documents to and from the JSON shape graphql-js produces, e.g.
+//! `{"kind":"Document","definitions":[...],"loc":{"start":0,"end":9}}`.
+//!
+//! Fields graphql-js leaves `undefined` are omitted, and locations only keep
+//! their `start` and `end` offsets.
+
+use crate::language::ast::{
+    Document,
+    Definition,
+    OperationType,
+    VariableDefinition,
+    InputValueDefinition,
+    SelectionSet,
+    Selection,
+    Location,
+    Argument,
+    Directive,
+    Name,
+    Type,
+    Value,
+    ObjectField
+};
+use crate::language::errors::ParseError;
+use crate::language::kinds::Kinds;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use serde_json::{Map, Value as Json};
+
+pub fn to_json(document: &Document) -> Json {
+    node(document.kind, &document.loc, vec![
+        ("definitions", Json::Array(document.definitions.iter().map(definition_to_json).collect()))
+    ])
+}
+
+pub fn from_json(json: &Json) -> Result<Document, ParseError> {
+    let object = expect_kind(json, &["Document"])?;
+    Ok(Document {
+        kind: Kinds::Document,
+        definitions: list(field(object, "definitions")?, definition_from_json)?,
+        loc: loc_from_json(object)?
+    })
+}
+
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        to_json(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Document, D::Error> {
+        let json = Json::deserialize(deserializer)?;
+        from_json(&json).map_err(|e| D::Error::custom(e.description))
+    }
+}
+
+// Serialization
+
+fn kind_name(kind: Kinds) -> &'static str {
+    match kind {
+        Kinds::Document             => "Document",
+        Kinds::OperationDefinition  => "OperationDefinition",
+        Kinds::FragmentDefinition   => "FragmentDefinition",
+        Kinds::VariableDefinition   => "VariableDefinition",
+        Kinds::InlineFragment       => "InlineFragment",
+        Kinds::FragmentSpread       => "FragmentSpread",
+        Kinds::SelectionSet         => "SelectionSet",
+        Kinds::Field                => "Field",
+        Kinds::Directive            => "Directive",
+        Kinds::Argument             => "Argument",
+        Kinds::Name                 => "Name",
+        Kinds::NonNullType          => "NonNullType",
+        Kinds::ListType             => "ListType",
+        Kinds::NamedType            => "NamedType",
+        Kinds::Int                  => "IntValue",
+        Kinds::Float                => "FloatValue",
+        Kinds::String               => "StringValue",
+        Kinds::Boolean              => "BooleanValue",
+        Kinds::Null                 => "NullValue",
+        Kinds::Enum                 => "EnumValue",
+        Kinds::Array                => "ListValue",
+        Kinds::Object               => "ObjectValue",
+        Kinds::ObjectField          => "ObjectField",
+        Kinds::Variable             => "Variable",
+        Kinds::DirectiveDefinition  => "DirectiveDefinition",
+        Kinds::InputValueDefinition => "InputValueDefinition"
+    }
+}
+
+fn node(kind: Kinds, loc: &Option<Location>, fields: Vec<(&str, Json)>) -> Json {
+    let mut object = Map::new();
+    object.insert("kind".to_owned(), Json::String(kind_name(kind).to_owned()));
+    for (key, value) in fields {
+        if !value.is_null() {
+            object.insert(key.to_owned(), value);
+        }
+    }
+    if let Some(ref loc) = *loc {
+        let mut location = Map::new();
+        location.insert("start".to_owned(), Json::from(loc.start));
+        location.insert("end".to_owned(), Json::from(loc.end));
+        object.insert("loc".to_owned(), Json::Object(location));
+    }
+    Json::Object(object)
+}
+
+fn array<T, F>(nodes: &[T], to_json: F) -> Json where F: Fn(&T) -> Json {
+    Json::Array(nodes.iter().map(to_json).collect())
+}
+
+fn optional<T, F>(node: &Option<T>, to_json: F) -> Json where F: Fn(&T) -> Json {
+    node.as_ref().map_or(Json::Null, to_json)
+}
+
+fn definition_to_json(definition: &Definition) -> Json {
+    match *definition {
+        Definition::Operation { kind, operation, ref name, ref variable_definitions, ref directives, ref selection_set, ref loc } => {
+            node(kind, loc, vec![
+                ("operation", Json::String(operation.to_string())),
+                ("name", optional(name, name_to_json)),
+                ("variableDefinitions", array(variable_definitions.as_ref().map_or(&[][..], |v| &v[..]), variable_definition_to_json)),
+                ("directives", array(directives, directive_to_json)),
+                ("selectionSet", selection_set_to_json(selection_set))
+            ])
+        },
+        Definition::Fragment { kind, ref name, ref variable_definitions, ref type_condition, ref directives, ref selection_set, ref loc } => {
+            node(kind, loc, vec![
+                ("name", name_to_json(name)),
+                ("variableDefinitions", optional(variable_definitions, |v| array(v, variable_definition_to_json))),
+                ("typeCondition", type_to_json(type_condition)),
+                ("directives", array(directives.as_ref().map_or(&[][..], |d| &d[..]), directive_to_json)),
+                ("selectionSet", selection_set_to_json(selection_set))
+            ])
+        },
+        Definition::Directive { kind, ref description, ref name, ref arguments, repeatable, ref locations, ref loc } => {
+            node(kind, loc, vec![
+                ("description", optional(description, value_to_json)),
+                ("name", name_to_json(name)),
+                ("arguments", array(arguments, input_value_definition_to_json)),
+                ("repeatable", Json::Bool(repeatable)),
+                ("locations", array(locations, name_to_json))
+            ])
+        }
+    }
+}
+
+fn variable_definition_to_json(definition: &VariableDefinition) -> Json {
+    node(definition.kind, &definition.loc, vec![
+        ("variable", value_to_json(&definition.variable)),
+        ("type", type_to_json(&definition.var_type)),
+        ("defaultValue", optional(&definition.default_value, value_to_json)),
+        ("directives", array(&definition.directives, directive_to_json))
+    ])
+}
+
+fn input_value_definition_to_json(definition: &InputValueDefinition) -> Json {
+    node(definition.kind, &definition.loc, vec![
+        ("description", optional(&definition.description, value_to_json)),
+        ("name", name_to_json(&definition.name)),
+        ("type", type_to_json(&definition.value_type)),
+        ("defaultValue", optional(&definition.default_value, value_to_json)),
+        ("directives", array(&definition.directives, directive_to_json))
+    ])
+}
+
+fn selection_set_to_json(selection_set: &SelectionSet) -> Json {
+    node(selection_set.kind, &selection_set.loc, vec![
+        ("selections", array(&selection_set.selections, selection_to_json))
+    ])
+}
+
+fn selection_to_json(selection: &Selection) -> Json {
+    match *selection {
+        Selection::Field { kind, ref alias, ref name, ref arguments, ref directives, ref selection_set, ref loc } => {
+            node(kind, loc, vec![
+                ("alias", optional(alias, name_to_json)),
+                ("name", name_to_json(name)),
+                ("arguments", array(arguments, argument_to_json)),
+                ("directives", array(directives, directive_to_json)),
+                ("selectionSet", optional(selection_set, selection_set_to_json))
+            ])
+        },
+        Selection::FragmentSpread { kind, ref name, ref directives, ref loc } => {
+            node(kind, loc, vec![
+                ("name", name_to_json(name)),
+                ("directives", array(directives.as_ref().map_or(&[][..], |d| &d[..]), directive_to_json))
+            ])
+        },
+        Selection::InlineFragment { kind, ref type_condition, ref directives, ref selection_set, ref loc } => {
+            node(kind, loc, vec![
+                ("typeCondition", optional(type_condition, type_to_json)),
+                ("directives", array(directives.as_ref().map_or(&[][..], |d| &d[..]), directive_to_json)),
+                ("selectionSet", selection_set_to_json(selection_set))
+            ])
+        }
+    }
+}
+
+fn argument_to_json(argument: &Argument) -> Json {
+    node(argument.kind, &argument.loc, vec![
+        ("name", name_to_json(&argument.name)),
+        ("value", value_to_json(&argument.value))
+    ])
+}
+
+fn directive_to_json(directive: &Directive) -> Json {
+    node(directive.kind, &directive.loc, vec![
+        ("name", name_to_json(&directive.name)),
+        ("arguments", array(directive.arguments.as_ref().map_or(&[][..], |a| &a[..]), argument_to_json))
+    ])
+}
+
+fn name_to_json(name: &Name) -> Json {
+    node(name.kind, &name.loc, vec![("value", Json::String(name.value.clone()))])
+}
+
+fn type_to_json(t_type: &Type) -> Json {
+    match *t_type {
+        Type::Named { kind, ref name, ref loc } => node(kind, loc, vec![("name", name_to_json(name))]),
+        Type::List { kind, ref t_type, ref loc } |
+        Type::NonNull { kind, ref t_type, ref loc } => node(kind, loc, vec![("type", type_to_json(t_type))])
+    }
+}
+
+fn value_to_json(value: &Value) -> Json {
+    match *value {
+        Value::IntValue { kind, ref value, ref loc } |
+        Value::FloatValue { kind, ref value, ref loc } |
+        Value::EnumValue { kind, ref value, ref loc } => node(kind, loc, vec![("value", Json::String(value.clone()))]),
+        Value::StringValue { kind, ref value, block, ref loc } => {
+            node(kind, loc, vec![("value", Json::String(value.clone())), ("block", Json::Bool(block))])
+        },
+        Value::BooleanValue { kind, value, ref loc } => node(kind, loc, vec![("value", Json::Bool(value))]),
+        Value::NullValue { kind, ref loc } => node(kind, loc, vec![]),
+        Value::ArrayValue { kind, ref values, ref loc } => node(kind, loc, vec![("values", array(values, value_to_json))]),
+        Value::ObjectValue { kind, ref fields, ref loc } => node(kind, loc, vec![("fields", array(fields, object_field_to_json))]),
+        Value::VariableValue { kind, ref name, ref loc } => node(kind, loc, vec![("name", name_to_json(name))])
+    }
+}
+
+fn object_field_to_json(field: &ObjectField) -> Json {
+    node(field.kind, &field.loc, vec![
+        ("name", name_to_json(&field.name)),
+        ("value", value_to_json(&field.value))
+    ])
+}
+
+// Deserialization
+
+type Object = Map<String, Json>;
+
+/// Checks that `json` is a node of one of the `expected` kinds.
+fn expect_kind<'a>(json: &'a Json, expected: &[&str]) -> Result<&'a Object, ParseError> {
+    let object = match *json {
+        Json::Object(ref object) => object,
+        _ => return parse_error!("Expected a {} node, found {}", expected.join(" or "), json)
+    };
+    let kind = kind(object)?;
+    if !expected.contains(&kind) {
+        return parse_error!("Expected a {} node, found kind '{}'", expected.join(" or "), kind);
+    }
+    Ok(object)
+}
+
+fn kind(object: &Object) -> Result<&str, ParseError> {
+    match object.get("kind") {
+        Some(Json::String(kind)) => Ok(kind),
+        _ => parse_error!("Expected a node with a kind, found {}", Json::Object(object.clone()))
+    }
+}
+
+fn field<'a>(object: &'a Object, key: &str) -> Result<&'a Json, ParseError> {
+    match object.get(key) {
+        Some(value) if !value.is_null() => Ok(value),
+        _ => parse_error!("Missing '{}' in {} node", key, kind(object)?)
+    }
+}
+
+fn maybe<'a, T, F>(object: &'a Object, key: &str, from_json: F) -> Result<Option<T>, ParseError>
+    where F: Fn(&'a Json) -> Result<T, ParseError> {
+    match object.get(key) {
+        Some(value) if !value.is_null() => from_json(value).map(Some),
+        _ => Ok(None)
+    }
+}
+
+fn list<'a, T, F>(json: &'a Json, from_json: F) -> Result<Vec<T>, ParseError>
+    where F: Fn(&'a Json) -> Result<T, ParseError> {
+    match *json {
+        Json::Array(ref values) => values.iter().map(from_json).collect(),
+        _ => parse_error!("Expected a list, found {}", json)
+    }
+}
+
+fn string(json: &Json) -> Result<String, ParseError> {
+    match *json {
+        Json::String(ref value) => Ok(value.clone()),
+        _ => parse_error!("Expected a string, found {}", json)
+    }
+}
+
+fn boolean(json: &Json) -> Result<bool, ParseError> {
+    match *json {
+        Json::Bool(value) => Ok(value),
+        _ => parse_error!("Expected a boolean, found {}", json)
+    }
+}
+
+fn offset(loc: &Object, key: &str) -> Result<usize, ParseError> {
+    match loc.get(key).and_then(Json::as_u64) {
+        Some(offset) => Ok(offset as usize),
+        None => parse_error!("Expected an offset for '{}' in loc", key)
+    }
+}
+
+fn loc_from_json(object: &Object) -> Result<Option<Location>, ParseError> {
+    match object.get("loc") {
+        Some(Json::Object(loc)) => {
+            Ok(Some(Location {
+                start: offset(loc, "start")?,
+                end: offset(loc, "end")?,
+                source: None
+            }))
+        },
+        Some(Json::Null) | None => Ok(None),
+        Some(loc) => parse_error!("Expected a loc, found {}", loc)
+    }
+}
+
+fn definition_from_json(json: &Json) -> Result<Definition, ParseError> {
+    let object = expect_kind(json, &["OperationDefinition", "FragmentDefinition", "DirectiveDefinition"])?;
+    match kind(object)? {
+        "OperationDefinition" => {
+            let operation = match &string(field(object, "operation")?)?[..] {
+                "query"        => OperationType::Query,
+                "mutation"     => OperationType::Mutation,
+                "subscription" => OperationType::Subscription,
+                operation      => return parse_error!("Unknown operation '{}'", operation)
+            };
+            let name = maybe(object, "name", name_from_json)?;
+            let variable_definitions = maybe(object, "variableDefinitions", |v| list(v, variable_definition_from_json))?;
+            let directives = maybe(object, "directives", |d| list(d, directive_from_json))?.unwrap_or_default();
+            // the parser only leaves the variables out of the query shorthand
+            let shorthand = operation == OperationType::Query && name.is_none() && directives.is_empty()
+                && variable_definitions.as_ref().is_none_or(|v| v.is_empty());
+            Ok(Definition::Operation {
+                kind: Kinds::OperationDefinition,
+                operation,
+                name,
+                variable_definitions: if shorthand { None } else { Some(variable_definitions.unwrap_or_default()) },
+                directives,
+                selection_set: selection_set_from_json(field(object, "selectionSet")?)?,
+                loc: loc_from_json(object)?
+            })
+        },
+        "FragmentDefinition" => {
+            Ok(Definition::Fragment {
+                kind: Kinds::FragmentDefinition,
+                name: name_from_json(field(object, "name")?)?,
+                variable_definitions: maybe(object, "variableDefinitions", |v| list(v, variable_definition_from_json))?,
+                type_condition: type_from_json(field(object, "typeCondition")?)?,
+                directives: Some(maybe(object, "directives", |d| list(d, directive_from_json))?.unwrap_or_default()),
+                selection_set: selection_set_from_json(field(object, "selectionSet")?)?,
+                loc: loc_from_json(object)?
+            })
+        },
+        _ => {
+            Ok(Definition::Directive {
+                kind: Kinds::DirectiveDefinition,
+                description: maybe(object, "description", value_from_json)?,
+                name: name_from_json(field(object, "name")?)?,
+                arguments: maybe(object, "arguments", |a| list(a, input_value_definition_from_json))?.unwrap_or_default(),
+                repeatable: maybe(object, "repeatable", boolean)?.unwrap_or(false),
+                locations: list(field(object, "locations")?, name_from_json)?,
+                loc: loc_from_json(object)?
+            })
+        }
+    }
+}
+
+fn variable_definition_from_json(json: &Json) -> Result<VariableDefinition, ParseError> {
+    let object = expect_kind(json, &["VariableDefinition"])?;
+    Ok(VariableDefinition {
+        kind: Kinds::VariableDefinition,
+        variable: value_from_json(field(object, "variable")?)?,
+        var_type: type_from_json(field(object, "type")?)?,
+        default_value: maybe(object, "defaultValue", value_from_json)?,
+        directives: maybe(object, "directives", |d| list(d, directive_from_json))?.unwrap_or_default(),
+        loc: loc_from_json(object)?
+    })
+}
+
+fn input_value_definition_from_json(json: &Json) -> Result<InputValueDefinition, ParseError> {
+    let object = expect_kind(json, &["InputValueDefinition"])?;
+    Ok(InputValueDefinition {
+        kind: Kinds::InputValueDefinition,
+        description: maybe(object, "description", value_from_json)?,
+        name: name_from_json(field(object, "name")?)?,
+        value_type: type_from_json(field(object, "type")?)?,
+        default_value: maybe(object, "defaultValue", value_from_json)?,
+        directives: maybe(object, "directives", |d| list(d, directive_from_json))?.unwrap_or_default(),
+        loc: loc_from_json(object)?
+    })
+}
+
+fn selection_set_from_json(json: &Json) -> Result<SelectionSet, ParseError> {
+    let object = expect_kind(json, &["SelectionSet"])?;
+    Ok(SelectionSet {
+        kind: Kinds::SelectionSet,
+        selections: list(field(object, "selections")?, selection_from_json)?,
+        loc: loc_from_json(object)?
+    })
+}
+
+fn selection_from_json(json: &Json) -> Result<Selection, ParseError> {
+    let object = expect_kind(json, &["Field", "FragmentSpread", "InlineFragment"])?;
+    let directives = maybe(object, "directives", |d| list(d, directive_from_json))?.unwrap_or_default();
+    match kind(object)? {
+        "Field" => {
+            Ok(Selection::Field {
+                kind: Kinds::Field,
+                alias: maybe(object, "alias", name_from_json)?,
+                name: name_from_json(field(object, "name")?)?,
+                arguments: maybe(object, "arguments", |a| list(a, argument_from_json))?.unwrap_or_default(),
+                directives,
+                selection_set: maybe(object, "selectionSet", selection_set_from_json)?,
+                loc: loc_from_json(object)?
+            })
+        },
+        "FragmentSpread" => {
+            Ok(Selection::FragmentSpread {
+                kind: Kinds::FragmentSpread,
+                name: name_from_json(field(object, "name")?)?,
+                directives: Some(directives),
+                loc: loc_from_json(object)?
+            })
+        },
+        _ => {
+            Ok(Selection::InlineFragment {
+                kind: Kinds::InlineFragment,
+                type_condition: maybe(object, "typeCondition", type_from_json)?,
+                directives: Some(directives),
+                selection_set: selection_set_from_json(field(object, "selectionSet")?)?,
+                loc: loc_from_json(object)?
+            })
+        }
+    }
+}
+
+fn argument_from_json(json: &Json) -> Result<Argument, ParseError> {
+    let object = expect_kind(json, &["Argument"])?;
+    Ok(Argument {
+        kind: Kinds::Argument,
+        name: name_from_json(field(object, "name")?)?,
+        value: value_from_json(field(object, "value")?)?,
+        loc: loc_from_json(object)?
+    })
+}
+
+fn directive_from_json(json: &Json) -> Result<Directive, ParseError> {
+    let object = expect_kind(json, &["Directive"])?;
+    Ok(Directive {
+        kind: Kinds::Directive,
+        name: name_from_json(field(object, "name")?)?,
+        arguments: Some(maybe(object, "arguments", |a| list(a, argument_from_json))?.unwrap_or_default()),
+        loc: loc_from_json(object)?
+    })
+}
+
+fn name_from_json(json: &Json) -> Result<Name, ParseError> {
+    let object = expect_kind(json, &["Name"])?;
+    Ok(Name {
+        kind: Kinds::Name,
+        value: string(field(object, "value")?)?,
+        loc: loc_from_json(object)?
+    })
+}
+
+fn type_from_json(json: &Json) -> Result<Type, ParseError> {
+    let object = expect_kind(json, &["NamedType", "ListType", "NonNullType"])?;
+    let loc = loc_from_json(object)?;
+    match kind(object)? {
+        "NamedType" => Ok(Type::Named { kind: Kinds::NamedType, name: name_from_json(field(object, "name")?)?, loc }),
+        "ListType"  => Ok(Type::List { kind: Kinds::ListType, t_type: Box::new(type_from_json(field(object, "type")?)?), loc }),
+        _           => Ok(Type::NonNull { kind: Kinds::NonNullType, t_type: Box::new(type_from_json(field(object, "type")?)?), loc })
+    }
+}
+
+fn value_from_json(json: &Json) -> Result<Value, ParseError> {
+    let object = expect_kind(json, &[
+        "IntValue", "FloatValue", "StringValue", "BooleanValue", "NullValue", "EnumValue",
+        "ListValue", "ObjectValue", "Variable"
+    ])?;
+    let loc = loc_from_json(object)?;
+    Ok(match kind(object)? {
+        "IntValue"     => Value::IntValue { kind: Kinds::Int, value: string(field(object, "value")?)?, loc },
+        "FloatValue"   => Value::FloatValue { kind: Kinds::Float, value: string(field(object, "value")?)?, loc },
+        "StringValue"  => Value::StringValue {
+            kind: Kinds::String,
+            value: string(field(object, "value")?)?,
+            block: maybe(object, "block", boolean)?.unwrap_or(false),
+            loc
+        },
+        "BooleanValue" => Value::BooleanValue { kind: Kinds::Boolean, value: boolean(field(object, "value")?)?, loc },
+        "NullValue"    => Value::NullValue { kind: Kinds::Null, loc },
+        "EnumValue"    => Value::EnumValue { kind: Kinds::Enum, value: string(field(object, "value")?)?, loc },
+        "ListValue"    => Value::ArrayValue { kind: Kinds::Array, values: list(field(object, "values")?, value_from_json)?, loc },
+        "ObjectValue"  => Value::ObjectValue { kind: Kinds::Object, fields: list(field(object, "fields")?, object_field_from_json)?, loc },
+        _              => Value::VariableValue { kind: Kinds::Variable, name: name_from_json(field(object, "name")?)?, loc }
+    })
+}
+
+fn object_field_from_json(json: &Json) -> Result<ObjectField, ParseError> {
+    let object = expect_kind(json, &["ObjectField"])?;
+    Ok(ObjectField {
+        kind: Kinds::ObjectField,
+        name: name_from_json(field(object, "name")?)?,
+        value: value_from_json(field(object, "value")?)?,
+        loc: loc_from_json(object)?
+    })
+}
@@ -26,9 +26,13 @@ pub enum Kinds {
     Float,
     String,
     Boolean,
+    Null,
     Enum,
     Array,
     Object,
     ObjectField,
-    Variable
+    Variable,
+
+    DirectiveDefinition,
+    InputValueDefinition
 }
@@ -1,6 +1,15 @@
+use crate::language::errors::LexError;
+
+use std::char;
+use std::sync::Arc;
+
+/// The text of a GraphQL document.
+///
+/// The body is shared, so cloning a `Source` (which every `Location` does)
+/// is cheap regardless of the document size.
 #[derive(PartialEq, Debug, Clone)]
 pub struct Source {
-    body: String,
+    body: Arc<String>,
     name: Option<String>
 }
 
@@ -11,10 +20,65 @@ impl Source {
 
     pub fn from(body: String) -> Source {
         Source {
-            body: body,
+            body: Arc::new(body),
             name: None
         }
     }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// The name of the source, usually the file it was read from. It's
+    /// included in parse errors and kept by every `Location`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|name| &name[..])
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(name.to_owned());
+    }
+
+    /// Translates a byte offset into the body into a line and column.
+    pub fn location(&self, position: usize) -> SourceLocation {
+        let mut line = 1;
+        let mut line_start = 0;
+        let bytes = self.body.as_bytes();
+        let end = position.min(bytes.len());
+        let mut i = 0;
+        while i < end {
+            match bytes[i] {
+                b'\n' => {
+                    line += 1;
+                    line_start = i + 1;
+                },
+                b'\r' => {
+                    if i + 1 < bytes.len() && bytes[i + 1] == b'\n' {
+                        i += 1;
+                    }
+                    line += 1;
+                    line_start = i + 1;
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+        let column = match self.body.get(line_start..end) {
+            Some(text) => text.chars().count() + 1,
+            None       => end - line_start + 1
+        };
+        SourceLocation {
+            line,
+            column
+        }
+    }
+}
+
+/// A one-based line and column within a `Source`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
@@ -37,16 +101,35 @@ pub enum TokenKind {
     Variable,
     Int,
     Float,
-    String
+    String,
+    BlockString,
+
+    // Ignored tokens, only produced by a lossless lexer
+    Whitespace,
+    Comma,
+    Comment
+}
+
+impl TokenKind {
+    /// Whether the token carries no meaning and is skipped by the parser.
+    pub fn is_ignored(&self) -> bool {
+        matches!(*self, TokenKind::Whitespace | TokenKind::Comma | TokenKind::Comment)
+    }
 }
 
 #[derive(PartialEq, Debug)]
 pub enum NameKind {
     Query,
     Mutation,
-    Fragment
+    Subscription,
+    Fragment,
+    Directive
 }
 
+/// A lexed token. `start` and `end` are byte offsets into the source body.
+///
+/// `value` holds the interpreted value of names, numbers and strings (with
+/// escapes resolved) and the text of comments.
 #[derive(PartialEq, Debug, Clone)]
 pub struct Token {
     pub kind: TokenKind,
@@ -58,31 +141,44 @@ pub struct Token {
 impl Token {
     fn make(kind: TokenKind, start: usize, end: usize) -> Token {
         Token {
-            kind: kind,
-            start: start,
-            end: end,
+            kind,
+            start,
+            end,
             value: None
         }
     }
 
     fn make_char(kind: TokenKind, start: usize) -> Token {
         Token {
-            kind: kind,
-            start: start,
+            kind,
+            start,
             end: start + 1,
             value: None
         }
     }
 
+    fn make_value(kind: TokenKind, start: usize, end: usize, value: String) -> Token {
+        Token {
+            kind,
+            start,
+            end,
+            value: Some(value)
+        }
+    }
+
     pub fn name_kind_from_value(&self) -> Option<NameKind> {
         match self.value {
             Some(ref v) => {
-                if *v == "mutation".to_string() {
+                if v == "mutation" {
                     Some(NameKind::Mutation)
-                } else if *v == "query".to_string() {
+                } else if v == "query" {
                     Some(NameKind::Query)
-                } else if *v == "fragment".to_string() {
+                } else if v == "subscription" {
+                    Some(NameKind::Subscription)
+                } else if v == "fragment" {
                     Some(NameKind::Fragment)
+                } else if v == "directive" {
+                    Some(NameKind::Directive)
                 } else {
                     None
                 }
@@ -92,304 +188,374 @@ impl Token {
     }
 }
 
+/// Splits a `Source` into tokens.
+///
+/// A `Lexer` is an iterator that ends with a single `EOF` token, or stops
+/// after the first `LexError`. A lossless lexer also yields whitespace,
+/// comma and comment tokens, so the concatenated token text reproduces the
+/// source exactly.
 pub struct Lexer {
-    prev_position: usize,
-    source: Source,
+    source:   Source,
+    position: usize,
+    lossless: bool,
+    finished: bool
 }
 
 impl Lexer {
     pub fn lex(source: Source) -> Lexer {
         Lexer {
-            prev_position: 0,
-            source: source,
+            source,
+            position: 0,
+            lossless: false,
+            finished: false
         }
     }
 
-    pub fn next(&mut self, reset_position: Option<usize>) -> Token {
-        let token = match reset_position {
-            Some(i) => Lexer::read_token(&self.source, i),
-            None    => Lexer::read_token(&self.source, self.prev_position)
-        };
-
-        self.prev_position = token.end;
-        token
-    }
-
-    fn read_token(source: &Source, from_position: usize) -> Token {
-        let ref body = source.body;
-
-        let position = Lexer::position_after_whitespace(body, from_position);
-        let mut bytes = body.bytes();
-        let c = bytes.nth(position);
-        match c {
-            Some(code) => {
-                match code {
-                    // !
-                    33 => Token::make_char(TokenKind::Bang, position),
-                    // $
-                    36 => Token::make_char(TokenKind::Dollar, position),
-                    // (
-                    40 => Token::make_char(TokenKind::ParenL, position),
-                    // )
-                    41 => Token::make_char(TokenKind::ParenR, position),
-                    // .
-                    46 => {
-                        // test for ...
-                        let c1 = bytes.next().unwrap_or(0);
-                        let c2 = bytes.next().unwrap_or(0);
-                        if c1 == 46 && c2 == 46 {
-                            Token::make(TokenKind::Spread, position, position + 3)
-                        } else {
-                            // TODO throw error
-                            Token::make(TokenKind::EOF, position, position)
-                        }
-                    }
-                    // :
-                    58 => Token::make_char(TokenKind::Colon, position),
-                    // =
-                    61 => Token::make_char(TokenKind::Equals, position),
-                    // @
-                    64 => Token::make_char(TokenKind::At, position),
-                    // [
-                    91 => Token::make_char(TokenKind::BracketL, position),
-                    // ]
-                    93 => Token::make_char(TokenKind::BracketR, position),
-                    // {
-                    123 => Token::make_char(TokenKind::BraceL, position),
-                    // |
-                    124 => Token::make_char(TokenKind::Pipe, position),
-                    // }
-                    125 => Token::make_char(TokenKind::BraceR, position),
-                    // A-Z _ a-z
-                    65 ... 90 | 95 | 97 ... 122 => Lexer::read_name(source, position),
-
-                    // 0-9
-                    45 | 48 ... 57 => Lexer::read_number(source, position),
-
-                    // "
-                    34 => Lexer::read_string(source, position),
-
-                    // TODO throw error
-                    _ => Token::make(TokenKind::EOF, position, position)
-                }
-            },
-            _ => Token::make(TokenKind::EOF, position, position)
+    pub fn lossless(source: Source) -> Lexer {
+        Lexer {
+            lossless: true,
+            ..Lexer::lex(source)
         }
     }
 
-    fn read_string(source: &Source, start: usize) -> Token {
-        let ref body = source.body;
-        let mut position = start + 1;
-        let mut chunk_start = position;
-        let mut bytes = body.bytes();
-        let mut code = bytes.nth(position).unwrap();
-        let mut value = vec![];
-
-        while 
-            position < body.len() &&
-            code != 34 &&
-            code != 10 && code != 13 // TODO && code != 0x2028 && code != 0x2029
-        {
-            position += 1;
-            if code == 92 { // \
-                Lexer::push_bytes_to_byte_array(body, &mut value, chunk_start, position - 1);
-                code = bytes.next().unwrap();
-                match code {
-                    //34 | 47 | 92 | 98 | 102 | 110 | 114 | 116 => value.push(code),
-
-                    _ => { } // TODO throw
-                }
-                position += 1;
-                chunk_start = position;
-            }
-            code = bytes.next().unwrap_or(0);
-        }
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
 
-        if code != 34 {
-            // TODO throw
-        }
+    fn body(&self) -> &[u8] {
+        self.source.body.as_bytes()
+    }
 
-        Lexer::push_bytes_to_byte_array(body, &mut value, chunk_start, position);
-        Token {
-            kind: TokenKind::String,
-            start: start,
-            end: position + 1,
-            value: Some(String::from_utf8(value).unwrap())
-        }
+    fn byte(&self, position: usize) -> Option<u8> {
+        self.body().get(position).cloned()
     }
 
-    fn push_bytes_to_byte_array(body: &String, target: &mut Vec<u8>, start: usize, end: usize) {
-        let mut bytes = body.bytes();
-        let mut i = start;
-        target.push(bytes.nth(start).unwrap());
-        while i < end - 1 {
-            i += 1;
-            target.push(bytes.next().unwrap());
-        }
+    fn error<T>(&self, position: usize, description: String) -> Result<T, LexError> {
+        Err(LexError::new(description, position))
     }
 
-    fn read_name(source: &Source, position: usize) -> Token {
-        let ref body = source.body;
-        let mut bytes = body.bytes();
-        let body_len = body.len();
-        let mut end = position + 1;
-        let mut code = bytes.nth(end).unwrap();
-        while 
-            end != body_len &&
-            (
-                code == 95 || // _
-                (code >= 48 && code <= 57) || // 0-9
-                (code >= 65 && code <= 90) || // A-Z
-                (code >= 97 && code <= 122) // a-z
-            )
-        {
-            end += 1;
-            code = bytes.next().unwrap()
+    fn unexpected<T>(&self, position: usize) -> Result<T, LexError> {
+        match self.source.body[position..].chars().next() {
+            Some(c) => self.error(position, format!("Unexpected character {:?}", c)),
+            None    => self.error(position, "Unexpected end of input".to_string())
         }
+    }
 
-        let string = Lexer::substring_from_body(body, position, end);
+    fn read_token(&self, position: usize) -> Result<Token, LexError> {
+        let code = match self.byte(position) {
+            Some(code) => code,
+            None       => return Ok(Token::make(TokenKind::EOF, position, position))
+        };
 
-        Token {
-            kind:  TokenKind::Name,
-            start: position,
-            end:   end,
-            value: Some(string)
-        }
+        let token = match code {
+            b'!' => Token::make_char(TokenKind::Bang, position),
+            b'$' => Token::make_char(TokenKind::Dollar, position),
+            b'(' => Token::make_char(TokenKind::ParenL, position),
+            b')' => Token::make_char(TokenKind::ParenR, position),
+            b'.' if self.byte(position + 1) == Some(b'.') && self.byte(position + 2) == Some(b'.') => {
+                Token::make(TokenKind::Spread, position, position + 3)
+            },
+            b':' => Token::make_char(TokenKind::Colon, position),
+            b'=' => Token::make_char(TokenKind::Equals, position),
+            b'@' => Token::make_char(TokenKind::At, position),
+            b'[' => Token::make_char(TokenKind::BracketL, position),
+            b']' => Token::make_char(TokenKind::BracketR, position),
+            b'{' => Token::make_char(TokenKind::BraceL, position),
+            b'|' => Token::make_char(TokenKind::Pipe, position),
+            b'}' => Token::make_char(TokenKind::BraceR, position),
+            b'A'..=b'Z' | b'_' | b'a'..=b'z' => self.read_name(position),
+            b'-' | b'0'..=b'9' => self.read_number(position)?,
+            b'"' => {
+                if self.byte(position + 1) == Some(b'"') && self.byte(position + 2) == Some(b'"') {
+                    self.read_block_string(position)?
+                } else {
+                    self.read_string(position)?
+                }
+            },
+            _ => return self.unexpected(position)
+        };
+        Ok(token)
     }
 
-    fn substring_from_body(body: &String, start: usize, end: usize) -> String {
-        // TODO: Figure out a better way to get a simple substring
-        let mut bytes = body.bytes();
-        let mut vec = vec![];
-        let mut i = start;
-        vec.push(bytes.nth(i).unwrap());
-        while i < end - 1 {
-            vec.push(bytes.next().unwrap());
-            i += 1;
+    fn read_name(&self, start: usize) -> Token {
+        let mut end = start + 1;
+        while let Some(code) = self.byte(end) {
+            match code {
+                b'_' | b'0'..=b'9' | b'A'..=b'Z' | b'a'..=b'z' => end += 1,
+                _ => break
+            }
         }
-        String::from_utf8(vec).unwrap()
+        Token::make_value(TokenKind::Name, start, end, self.source.body[start..end].to_string())
     }
 
-    fn read_number(source: &Source, start: usize) -> Token {
-        let ref body = source.body;
-        let mut bytes = body.bytes();
-        let mut code = bytes.nth(start).unwrap();
+    fn read_number(&self, start: usize) -> Result<Token, LexError> {
         let mut position = start;
         let mut is_float = false;
 
-        if code == 45 { // -
-            code = bytes.next().unwrap();
+        if self.byte(position) == Some(b'-') {
             position += 1;
         }
 
-        if code == 48 { // 0
-            code = bytes.next().unwrap_or(0);
+        if self.byte(position) == Some(b'0') {
             position += 1;
-        } else if code >= 49 && code <= 57 { // 1 - 9
-            while code >= 48 && code <= 57 { // 0 - 9
-                code = bytes.next().unwrap_or(0);
-                position += 1;
-            }
         } else {
-            // TODO: throw invalid number
+            position = self.read_digits(position)?;
         }
 
-        if code == 46 { // .
+        if self.byte(position) == Some(b'.') {
             is_float = true;
+            position = self.read_digits(position + 1)?;
+        }
 
-            code = bytes.next().unwrap();
+        if let Some(b'e') | Some(b'E') = self.byte(position) {
+            is_float = true;
             position += 1;
-
-            if code >= 48 && code <= 57 { // 0-9
-                while code >= 48 && code <= 57 {
-                    code = bytes.next().unwrap_or(0);
-                    position += 1;
-                }
-            } else {
-                // TODO throw invalid number
+            if let Some(b'+') | Some(b'-') = self.byte(position) {
+                position += 1;
             }
+            position = self.read_digits(position)?;
         }
 
-        if code == 69 || code == 101 { // e or E
-            is_float = true;
+        let kind = if is_float { TokenKind::Float } else { TokenKind::Int };
+        Ok(Token::make_value(kind, start, position, self.source.body[start..position].to_string()))
+    }
 
-            code = bytes.next().unwrap();
+    fn read_digits(&self, start: usize) -> Result<usize, LexError> {
+        let mut position = start;
+        while let Some(b'0'..=b'9') = self.byte(position) {
             position += 1;
+        }
+        if position == start {
+            return match self.source.body[start..].chars().next() {
+                Some(c) => self.error(start, format!("Invalid number, expected digit but got {:?}", c)),
+                None    => self.error(start, "Invalid number, expected digit but got end of input".to_string())
+            };
+        }
+        Ok(position)
+    }
 
-            if code == 43 || code == 45 { // + -
-                code = bytes.next().unwrap();
-                position += 1;
+    fn read_string(&self, start: usize) -> Result<Token, LexError> {
+        let body = self.source.body.as_str();
+        let mut position = start + 1;
+        let mut chunk_start = position;
+        let mut value = String::new();
+
+        loop {
+            let code = match self.byte(position) {
+                Some(b'\n') | Some(b'\r') | None => return self.error(start, "Unterminated string".to_string()),
+                Some(code) => code
+            };
+
+            match code {
+                b'"' => {
+                    value.push_str(&body[chunk_start..position]);
+                    return Ok(Token::make_value(TokenKind::String, start, position + 1, value));
+                },
+                b'\\' => {
+                    value.push_str(&body[chunk_start..position]);
+                    let escaped = match self.byte(position + 1) {
+                        Some(b'"')  => '"',
+                        Some(b'/')  => '/',
+                        Some(b'\\') => '\\',
+                        Some(b'b')  => '\u{8}',
+                        Some(b'f')  => '\u{c}',
+                        Some(b'n')  => '\n',
+                        Some(b'r')  => '\r',
+                        Some(b't')  => '\t',
+                        Some(b'u')  => {
+                            // A high surrogate followed by an escaped low
+                            // surrogate stands for one character, as in JSON.
+                            let code_unit = hex_code_unit(body, position + 2);
+                            let low = code_unit.filter(|unit| (0xd800..0xdc00).contains(unit))
+                                .filter(|_| body[position + 6..].starts_with("\\u"))
+                                .and_then(|_| hex_code_unit(body, position + 8))
+                                .filter(|unit| (0xdc00..0xe000).contains(unit));
+                            let c = match (code_unit, low) {
+                                (Some(high), Some(low)) => {
+                                    position += 6;
+                                    char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+                                },
+                                (code_unit, _) => code_unit.and_then(char::from_u32)
+                            };
+                            match c {
+                                Some(c) => {
+                                    position += 4;
+                                    c
+                                },
+                                None => return self.error(position, "Invalid unicode escape sequence".to_string())
+                            }
+                        },
+                        _ => return self.error(position, "Invalid character escape sequence".to_string())
+                    };
+                    value.push(escaped);
+                    position += 2;
+                    chunk_start = position;
+                },
+                b'\t' => position += 1,
+                0..=0x1f => return self.error(position, "Invalid character within string".to_string()),
+                _ => position += 1
             }
+        }
+    }
 
-            if code >= 48 && code <= 57 { // 0-9
-                while code >= 48 && code <= 57 {
-                    code = bytes.next().unwrap_or(0);
-                    position += 1;
-                }
-            } else {
-                // TODO throw invalid number
+    fn read_block_string(&self, start: usize) -> Result<Token, LexError> {
+        let body = self.source.body.as_str();
+        let mut position = start + 3;
+        let mut chunk_start = position;
+        let mut raw = String::new();
+
+        loop {
+            match self.byte(position) {
+                None => return self.error(start, "Unterminated string".to_string()),
+                Some(b'"') if body[position..].starts_with("\"\"\"") => {
+                    raw.push_str(&body[chunk_start..position]);
+                    return Ok(Token::make_value(TokenKind::BlockString, start, position + 3, block_string_value(&raw)));
+                },
+                Some(b'\\') if body[position..].starts_with("\\\"\"\"") => {
+                    raw.push_str(&body[chunk_start..position]);
+                    raw.push_str("\"\"\"");
+                    position += 4;
+                    chunk_start = position;
+                },
+                Some(b'\t') | Some(b'\n') | Some(b'\r') => position += 1,
+                Some(0..=0x1f) => return self.error(position, "Invalid character within string".to_string()),
+                Some(_) => position += 1
             }
         }
+    }
 
-        let kind = match is_float {
-            true => TokenKind::Float,
-            false => TokenKind::Int
-        };
+    fn read_comment(&self, start: usize) -> Token {
+        let mut end = start + 1;
+        while let Some(code) = self.byte(end) {
+            if code == b'\n' || code == b'\r' {
+                break;
+            }
+            end += 1;
+        }
+        Token::make_value(TokenKind::Comment, start, end, self.source.body[start + 1..end].to_string())
+    }
 
-        Token {
-            kind: kind,
-            start: start,
-            end: position,
-            value: Some(Lexer::substring_from_body(body, start, position))
+    fn whitespace_len(&self, position: usize) -> usize {
+        match self.byte(position) {
+            Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => 1,
+            // byte order mark
+            Some(0xef) if self.body()[position..].starts_with(&[0xef, 0xbb, 0xbf]) => 3,
+            _ => 0
         }
     }
 
-    fn position_after_whitespace(body: &String, start_position: usize) -> usize {
-        let body_length = body.len();
-        let mut position = start_position;
-        let mut bytes = body.bytes();
-
-        let mut c = bytes.nth(position);
-        while position < body_length {
-            match c {
-                Some(mut code) => {
-                    if Lexer::is_whitespace(code) {
-                        position += 1;
-                    } else if code == 35 { // skip comments
-                        position += 1;
-                        while position < body_length {
-                            code = bytes.next().unwrap();
-                            position += 1;
-                            if code == 10 || code == 13 { // TODO || code == 0x2028 || code == 0x2029 {
-                                break;
-                            }
-                        }
-                    } else {
-                        break;
-                    }
-                },
-                None => {
-                    break
+    fn position_after_whitespace(&self, start: usize) -> usize {
+        let mut position = start;
+        loop {
+            match self.byte(position) {
+                Some(b',') => position += 1,
+                Some(b'#') => position = self.read_comment(position).end,
+                Some(_) if self.whitespace_len(position) > 0 => position += self.whitespace_len(position),
+                _ => return position
+            }
+        }
+    }
+
+    fn read_ignored_token(&self, start: usize) -> Option<Token> {
+        match self.byte(start) {
+            Some(b',') => Some(Token::make_char(TokenKind::Comma, start)),
+            Some(b'#') => Some(self.read_comment(start)),
+            _ => {
+                let mut end = start;
+                while self.whitespace_len(end) > 0 {
+                    end += self.whitespace_len(end);
+                }
+                if end > start {
+                    Some(Token::make(TokenKind::Whitespace, start, end))
+                } else {
+                    None
                 }
             }
-            c = bytes.next();
         }
-        position
     }
+}
+
+impl Iterator for Lexer {
+    type Item = Result<Token, LexError>;
 
-    fn is_whitespace(code: u8) -> bool {
-        code == 32 || code == 44 || code == 160 || // TODO:  code == 0x2028 || code == 0x2029 ||
-        (code > 8 && code < 14)
+    fn next(&mut self) -> Option<Result<Token, LexError>> {
+        if self.finished {
+            return None;
+        }
+
+        let result = if self.lossless {
+            match self.read_ignored_token(self.position) {
+                Some(token) => Ok(token),
+                None        => self.read_token(self.position)
+            }
+        } else {
+            let position = self.position_after_whitespace(self.position);
+            self.read_token(position)
+        };
+
+        match result {
+            Ok(ref token) => {
+                self.position = token.end;
+                self.finished = token.kind == TokenKind::EOF;
+            },
+            Err(_) => self.finished = true
+        }
+        Some(result)
     }
 }
 
+/// Produces the value of a block string from its raw content by removing
+/// the common indentation and any leading or trailing blank lines.
+pub fn block_string_value(raw: &str) -> String {
+    let lines: Vec<&str> = raw.split("\r\n").flat_map(|l| l.split(['\n', '\r'])).collect();
+
+    let common_indent = lines.iter().skip(1)
+        .filter_map(|line| {
+            let indent = leading_whitespace(line);
+            if indent < line.len() { Some(indent) } else { None }
+        })
+        .min();
+
+    let mut lines: Vec<&str> = lines.iter().enumerate().map(|(i, line)| {
+        match common_indent {
+            Some(indent) if i > 0 => if line.len() < indent { "" } else { &line[indent..] },
+            _ => line
+        }
+    }).collect();
+
+    while lines.first().is_some_and(|line| leading_whitespace(line) == line.len()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|line| leading_whitespace(line) == line.len()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// The four hex digits at `position` of a `\u` escape.
+fn hex_code_unit(body: &str, position: usize) -> Option<u32> {
+    body.get(position..position + 4)
+        .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+}
+
+fn leading_whitespace(line: &str) -> usize {
+    line.bytes().take_while(|&c| c == b' ' || c == b'\t').count()
+}
+
 #[cfg(test)]
 mod test {
     use env_logger;
     use super::*;
+    use crate::language::errors::LexError;
 
     fn lex_one(body: &str) -> Token {
-        Lexer::lex(Source::new(body)).next(None)
+        Lexer::lex(Source::new(body)).next().unwrap().unwrap()
+    }
+
+    fn lex_err(body: &str) -> LexError {
+        Lexer::lex(Source::new(body)).next().unwrap().err().unwrap()
     }
 
     #[test]
@@ -558,7 +724,7 @@ mod test {
 
         fn test_punct(punc: &str, kind: TokenKind) {
             assert_eq!(lex_one(punc), Token {
-                kind: kind,
+                kind,
                 start: 0,
                 end: 1,
                 value: None
@@ -607,7 +773,7 @@ mod test {
             kind: TokenKind::String,
             start: 0,
             end: 4,
-            value: Some(r#"\""#.to_string())
+            value: Some(r#"""#.to_string())
         });
 
         assert_eq!(lex_one(r#""quote \"""#), Token {
@@ -617,7 +783,90 @@ mod test {
             value: Some(r#"quote ""#.to_string())
         });
         
-        // TODO: A bunch more tests that are a pain in the ass
+        assert_eq!(lex_one(r#""escaped \n\r\b\t\f""#), Token {
+            kind: TokenKind::String,
+            start: 0,
+            end: 20,
+            value: Some("escaped \n\r\u{8}\t\u{c}".to_string())
+        });
+
+        assert_eq!(lex_one(r#""slashes \\ \/""#), Token {
+            kind: TokenKind::String,
+            start: 0,
+            end: 15,
+            value: Some("slashes \\ /".to_string())
+        });
+
+        assert_eq!(lex_one(r#""unicode \u1234\u5678\u90AB\uCDEF""#), Token {
+            kind: TokenKind::String,
+            start: 0,
+            end: 34,
+            value: Some("unicode \u{1234}\u{5678}\u{90AB}\u{CDEF}".to_string())
+        });
+
+        assert_eq!(lex_one(r#""surrogate pair \uD83D\uDE00""#), Token {
+            kind: TokenKind::String,
+            start: 0,
+            end: 29,
+            value: Some("surrogate pair \u{1F600}".to_string())
+        });
+    }
+
+    #[test]
+    fn it_lexes_block_strings() {
+        assert_eq!(lex_one(r#""""simple""""#), Token {
+            kind: TokenKind::BlockString,
+            start: 0,
+            end: 12,
+            value: Some("simple".to_string())
+        });
+
+        assert_eq!(lex_one(r#""""contains " quote""""#), Token {
+            kind: TokenKind::BlockString,
+            start: 0,
+            end: 22,
+            value: Some(r#"contains " quote"#.to_string())
+        });
+
+        assert_eq!(lex_one(r#""""contains \""" triplequote""""#), Token {
+            kind: TokenKind::BlockString,
+            start: 0,
+            end: 31,
+            value: Some(r#"contains """ triplequote"#.to_string())
+        });
+
+        assert_eq!(lex_one("\"\"\"\n\n        spans\n          multiple\n            lines\n\n        \"\"\""), Token {
+            kind: TokenKind::BlockString,
+            start: 0,
+            end: 68,
+            value: Some("spans\n  multiple\n    lines".to_string())
+        });
+
+        assert_eq!(lex_one(r#""""unescaped \n\r\b\t\f\u1234""""#), Token {
+            kind: TokenKind::BlockString,
+            start: 0,
+            end: 32,
+            value: Some(r#"unescaped \n\r\b\t\f\u1234"#.to_string())
+        });
+    }
+
+    #[test]
+    fn it_reports_lex_errors() {
+        assert_eq!(lex_err("\"unterminated"), LexError::new("Unterminated string".to_string(), 0));
+        assert_eq!(lex_err("\"multi\nline\""), LexError::new("Unterminated string".to_string(), 0));
+        assert_eq!(lex_err(r#""bad \x esc""#), LexError::new("Invalid character escape sequence".to_string(), 5));
+        assert_eq!(lex_err(r#""bad \u1 esc""#), LexError::new("Invalid unicode escape sequence".to_string(), 5));
+        assert_eq!(lex_err(r#""lone \uD83D esc""#), LexError::new("Invalid unicode escape sequence".to_string(), 6));
+        assert_eq!(lex_err(r#""lone \uDE00\uD83D esc""#), LexError::new("Invalid unicode escape sequence".to_string(), 6));
+        assert_eq!(lex_err(r#""bad \u+123 esc""#), LexError::new("Invalid unicode escape sequence".to_string(), 5));
+        assert_eq!(lex_err("\"\u{7}\""), LexError::new("Invalid character within string".to_string(), 1));
+        assert_eq!(lex_err("\"\"\"unterminated"), LexError::new("Unterminated string".to_string(), 0));
+        assert_eq!(lex_err("1."), LexError::new("Invalid number, expected digit but got end of input".to_string(), 2));
+        assert_eq!(lex_err("-A"), LexError::new("Invalid number, expected digit but got 'A'".to_string(), 1));
+        assert_eq!(lex_err("1.23e"), LexError::new("Invalid number, expected digit but got end of input".to_string(), 5));
+        assert_eq!(lex_err(".."), LexError::new("Unexpected character '.'".to_string(), 0));
+        assert_eq!(lex_err("?"), LexError::new("Unexpected character '?'".to_string(), 0));
+        assert_eq!(lex_err("\u{203B}"), LexError::new("Unexpected character '\u{203B}'".to_string(), 0));
     }
 
     #[test]
@@ -626,9 +875,56 @@ mod test {
 
         let source = Source::new("{{");
         let mut lexer = Lexer::lex(source);
-        lexer.next(None);
-        lexer.next(None);
-        assert_eq!(lexer.next(None).kind, TokenKind::EOF);
+        lexer.next();
+        lexer.next();
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::EOF);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn it_stops_after_an_error() {
+        let mut lexer = Lexer::lex(Source::new("a ? b"));
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Name);
+        assert!(lexer.next().unwrap().is_err());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn it_iterates_over_tokens() {
+        let kinds: Vec<TokenKind> = Lexer::lex(Source::new("query { a(b: $c) }"))
+            .map(|token| token.unwrap().kind)
+            .collect();
+        assert_eq!(kinds, vec![
+            TokenKind::Name, TokenKind::BraceL, TokenKind::Name, TokenKind::ParenL, TokenKind::Name,
+            TokenKind::Colon, TokenKind::Dollar, TokenKind::Name, TokenKind::ParenR, TokenKind::BraceR,
+            TokenKind::EOF
+        ]);
+    }
+
+    #[test]
+    fn it_lexes_losslessly() {
+        let source = Source::new("{ a, # note\n\tb }");
+        let tokens: Vec<Token> = Lexer::lossless(source.clone()).map(|token| token.unwrap()).collect();
+
+        let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(kinds, vec![
+            TokenKind::BraceL, TokenKind::Whitespace, TokenKind::Name, TokenKind::Comma,
+            TokenKind::Whitespace, TokenKind::Comment, TokenKind::Whitespace, TokenKind::Name,
+            TokenKind::Whitespace, TokenKind::BraceR, TokenKind::EOF
+        ]);
+        assert_eq!(tokens[5].value, Some(" note".to_string()));
+
+        let text: String = tokens.iter().map(|token| &source.body()[token.start..token.end]).collect();
+        assert_eq!(text, source.body());
+    }
+
+    #[test]
+    fn it_locates_positions() {
+        let source = Source::new("a\nbc\r\nd\re");
+        assert_eq!(source.location(0), SourceLocation { line: 1, column: 1 });
+        assert_eq!(source.location(3), SourceLocation { line: 2, column: 2 });
+        assert_eq!(source.location(6), SourceLocation { line: 3, column: 1 });
+        assert_eq!(source.location(8), SourceLocation { line: 4, column: 1 });
     }
 
     // TODO: exception based tests
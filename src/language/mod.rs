@@ -1,6 +1,13 @@
 #[macro_use]
 pub mod errors;
 pub mod ast;
+pub mod fold;
+#[cfg(feature = "serde")]
+pub mod json;
 pub mod kinds;
 pub mod lexer;
+pub mod normalize;
+pub mod operation;
 pub mod parser;
+pub mod printer;
+pub mod visitor;
@@ -0,0 +1,234 @@
+use crate::language::ast::{
+    Document,
+    Definition,
+    SelectionSet,
+    Selection,
+    Argument,
+    Directive,
+    Type,
+    Value
+};
+use crate::language::fold::{self, Fold};
+use crate::language::kinds::Kinds;
+use crate::language::printer::{print_compact, print_compact_selection};
+
+use std::collections::HashSet;
+
+#[derive(Clone, Copy)]
+pub struct NormalizeOptions {
+    drop_aliases:  bool,
+    hide_literals: bool
+}
+
+impl NormalizeOptions {
+    pub fn new() -> NormalizeOptions {
+        NormalizeOptions {
+            drop_aliases:  false,
+            hide_literals: true
+        }
+    }
+
+    /// Removes field aliases, so `a: field` and `b: field` normalize alike.
+    pub fn set_drop_aliases(&mut self, drop_aliases: bool) {
+        self.drop_aliases = drop_aliases;
+    }
+
+    /// Replaces int, float and string literals with `0`, `0` and `""`, and
+    /// list and object literals with `[]` and `{}`. Booleans, enums and
+    /// nulls are kept since they usually select a different code path.
+    pub fn set_hide_literals(&mut self, hide_literals: bool) {
+        self.hide_literals = hide_literals;
+    }
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> NormalizeOptions {
+        NormalizeOptions::new()
+    }
+}
+
+/// Rewrites `document` into a canonical form, so that operations which only
+/// differ in formatting, field order or literal values become equal.
+///
+/// Fragments no operation uses are removed, and definitions, selections,
+/// arguments, directives and variable definitions are sorted.
+pub fn normalize(document: Document, options: NormalizeOptions) -> Document {
+    let mut document = Normalizer { options }.fold_document(document);
+    remove_unused_fragments(&mut document);
+    document.definitions.sort_by(|a, b| definition_key(a).cmp(&definition_key(b)));
+    document
+}
+
+/// The compactly printed, normalized document.
+pub fn signature(document: Document, options: NormalizeOptions) -> String {
+    print_compact(&normalize(document, options))
+}
+
+/// A 64 bit FNV-1a hash of the signature. It only depends on the signature
+/// text, so it's stable across runs, platforms and releases.
+pub fn signature_hash(document: Document, options: NormalizeOptions) -> u64 {
+    fnv1a(signature(document, options).as_bytes())
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+struct Normalizer {
+    options: NormalizeOptions
+}
+
+impl Fold for Normalizer {
+    fn fold_definition(&mut self, node: Definition) -> Vec<Definition> {
+        let mut definitions = fold::fold_definition(self, node);
+        for definition in &mut definitions {
+            match *definition {
+                Definition::Operation { variable_definitions: Some(ref mut variables), ref mut directives, .. } => {
+                    variables.sort_by(|a, b| variable_name(&a.variable).cmp(variable_name(&b.variable)));
+                    sort_directives(directives);
+                },
+                Definition::Operation { ref mut directives, .. } => sort_directives(directives),
+                Definition::Fragment { directives: Some(ref mut directives), .. } => sort_directives(directives),
+                _ => {}
+            }
+        }
+        definitions
+    }
+
+    fn fold_selection_set(&mut self, node: SelectionSet) -> SelectionSet {
+        let mut node = fold::fold_selection_set(self, node);
+        node.selections.sort_by_cached_key(selection_key);
+        node
+    }
+
+    fn fold_selection(&mut self, node: Selection) -> Vec<Selection> {
+        let mut selections = fold::fold_selection(self, node);
+        for selection in &mut selections {
+            match *selection {
+                Selection::Field { ref mut alias, ref mut arguments, ref mut directives, .. } => {
+                    if self.options.drop_aliases {
+                        *alias = None;
+                    }
+                    sort_arguments(arguments);
+                    sort_directives(directives);
+                },
+                Selection::FragmentSpread { directives: Some(ref mut directives), .. } |
+                Selection::InlineFragment { directives: Some(ref mut directives), .. } => sort_directives(directives),
+                _ => {}
+            }
+        }
+        selections
+    }
+
+    fn fold_directive(&mut self, node: Directive) -> Vec<Directive> {
+        let mut directives = fold::fold_directive(self, node);
+        for directive in &mut directives {
+            if let Some(ref mut arguments) = directive.arguments {
+                sort_arguments(arguments);
+            }
+        }
+        directives
+    }
+
+    fn fold_value(&mut self, node: Value) -> Value {
+        if !self.options.hide_literals {
+            return fold::fold_value(self, node);
+        }
+        match node {
+            Value::IntValue { loc, .. } |
+            Value::FloatValue { loc, .. } => Value::IntValue { kind: Kinds::Int, value: "0".to_owned(), loc },
+            Value::StringValue { kind, loc, .. } => Value::StringValue { kind, value: String::new(), block: false, loc },
+            Value::ArrayValue { kind, loc, .. } => Value::ArrayValue { kind, values: vec![], loc },
+            Value::ObjectValue { kind, loc, .. } => Value::ObjectValue { kind, fields: vec![], loc },
+            node => fold::fold_value(self, node)
+        }
+    }
+}
+
+fn sort_arguments(arguments: &mut [Argument]) {
+    arguments.sort_by(|a, b| a.name.value.cmp(&b.name.value));
+}
+
+fn sort_directives(directives: &mut [Directive]) {
+    directives.sort_by(|a, b| a.name.value.cmp(&b.name.value));
+}
+
+fn variable_name(variable: &Value) -> &str {
+    match *variable {
+        Value::VariableValue { ref name, .. } => &name.value,
+        _ => ""
+    }
+}
+
+fn type_name(t_type: &Type) -> &str {
+    match *t_type {
+        Type::Named { ref name, .. } => &name.value,
+        Type::List { ref t_type, .. } |
+        Type::NonNull { ref t_type, .. } => type_name(t_type)
+    }
+}
+
+fn definition_key(definition: &Definition) -> (u8, &str) {
+    match *definition {
+        Definition::Operation { ref name, .. } => (0, name.as_ref().map_or("", |n| &n.value[..])),
+        Definition::Fragment { ref name, .. } => (1, &name.value),
+        Definition::Directive { ref name, .. } => (2, &name.value)
+    }
+}
+
+/// Orders selections by kind, name and alias, then by the whole printed
+/// selection, so selections differing only in their arguments, directives
+/// or sub-selections don't keep their source order.
+fn selection_key(selection: &Selection) -> (u8, String, String, String) {
+    let (kind, name, alias) = match *selection {
+        Selection::Field { ref name, ref alias, .. } => (0, &name.value[..], alias.as_ref().map_or("", |a| &a.value[..])),
+        Selection::FragmentSpread { ref name, .. } => (1, &name.value[..], ""),
+        Selection::InlineFragment { ref type_condition, .. } => (2, type_condition.as_ref().map_or("", type_name), "")
+    };
+    (kind, name.to_owned(), alias.to_owned(), print_compact_selection(selection))
+}
+
+/// Drops fragment definitions that aren't reachable from any operation.
+fn remove_unused_fragments(document: &mut Document) {
+    let mut used = HashSet::new();
+    let mut pending = vec![];
+    for definition in &document.definitions {
+        if let Definition::Operation { ref selection_set, .. } = *definition {
+            spreads(selection_set, &mut pending);
+        }
+    }
+    while let Some(name) = pending.pop() {
+        if !used.insert(name.clone()) {
+            continue;
+        }
+        for definition in &document.definitions {
+            match *definition {
+                Definition::Fragment { name: ref fragment, ref selection_set, .. } if fragment.value == name => {
+                    spreads(selection_set, &mut pending);
+                },
+                _ => {}
+            }
+        }
+    }
+
+    document.definitions.retain(|definition| match *definition {
+        Definition::Fragment { ref name, .. } => used.contains(&name.value),
+        _ => true
+    });
+}
+
+fn spreads(selection_set: &SelectionSet, names: &mut Vec<String>) {
+    for selection in &selection_set.selections {
+        match *selection {
+            Selection::Field { selection_set: Some(ref selection_set), .. } |
+            Selection::InlineFragment { ref selection_set, .. } => spreads(selection_set, names),
+            Selection::FragmentSpread { ref name, .. } => names.push(name.value.clone()),
+            _ => {}
+        }
+    }
+}
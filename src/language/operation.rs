@@ -0,0 +1,80 @@
+use crate::language::ast::{Document, Definition, SelectionSet, Selection};
+use crate::language::errors::OperationError;
+
+use std::collections::HashSet;
+
+/// Picks the operation to execute from `document`.
+///
+/// With a name, that operation is returned. Without one, the document must
+/// contain exactly one operation.
+pub fn get_operation<'a>(document: &'a Document, name: Option<&str>) -> Result<&'a Definition, OperationError> {
+    let mut operations = document.definitions.iter().filter(|definition| matches!(**definition, Definition::Operation { .. }));
+
+    match name {
+        Some(name) => {
+            operations.find(|operation| match **operation {
+                Definition::Operation { name: Some(ref operation_name), .. } => operation_name.value == name,
+                _ => false
+            }).ok_or_else(|| OperationError::new(format!("Unknown operation named \"{}\".", name)))
+        },
+        None => {
+            match (operations.next(), operations.next()) {
+                (Some(operation), None) => Ok(operation),
+                (Some(_), Some(_)) => Err(OperationError::new("Must provide operation name if query contains multiple operations.".to_owned())),
+                (None, _) => Err(OperationError::new("Must provide an operation.".to_owned()))
+            }
+        }
+    }
+}
+
+/// The fragment definitions `definition` references, directly or through
+/// other fragments, in document order.
+pub fn get_fragments<'a>(document: &'a Document, definition: &'a Definition) -> Result<Vec<&'a Definition>, OperationError> {
+    let mut used = HashSet::new();
+    let mut pending = vec![];
+    spreads(selection_set(definition), &mut pending);
+
+    while let Some(name) = pending.pop() {
+        if !used.insert(name) {
+            continue;
+        }
+        match find_fragment(document, name) {
+            Some(fragment) => spreads(selection_set(fragment), &mut pending),
+            None => return Err(OperationError::new(format!("Unknown fragment \"{}\".", name)))
+        }
+    }
+
+    Ok(document.definitions.iter().filter(|definition| match **definition {
+        Definition::Fragment { ref name, .. } => used.contains(&name.value[..]),
+        _ => false
+    }).collect())
+}
+
+fn find_fragment<'a>(document: &'a Document, name: &str) -> Option<&'a Definition> {
+    document.definitions.iter().find(|definition| match **definition {
+        Definition::Fragment { name: ref fragment, .. } => fragment.value == name,
+        _ => false
+    })
+}
+
+fn selection_set(definition: &Definition) -> Option<&SelectionSet> {
+    match *definition {
+        Definition::Operation { ref selection_set, .. } |
+        Definition::Fragment { ref selection_set, .. } => Some(selection_set),
+        Definition::Directive { .. } => None
+    }
+}
+
+fn spreads<'a>(selection_set: Option<&'a SelectionSet>, names: &mut Vec<&'a str>) {
+    let selection_set = match selection_set {
+        Some(selection_set) => selection_set,
+        None => return
+    };
+    for selection in &selection_set.selections {
+        match *selection {
+            Selection::Field { ref selection_set, .. } => spreads(selection_set.as_ref(), names),
+            Selection::InlineFragment { ref selection_set, .. } => spreads(Some(selection_set), names),
+            Selection::FragmentSpread { ref name, .. } => names.push(&name.value)
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use language::lexer::{Source, Lexer, Token, TokenKind, NameKind};
-use language::ast::{
+use crate::language::lexer::{Source, Lexer, Token, TokenKind, NameKind};
+use crate::language::ast::{
     Document,
     Definition,
+    OperationType,
+    InputValueDefinition,
     Directive,
     SelectionSet,
     Location,
@@ -13,40 +15,67 @@ use language::ast::{
     VariableDefinition,
     Name
 };
-use language::kinds::Kinds;
-use language::errors::{
+use crate::language::kinds::Kinds;
+use crate::language::errors::{
     ParseError,
 };
 
-use std::sync::RwLock;
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::mem;
 use std::result::Result;
 
+/// Directive locations accepted in a `directive @name on ...` definition.
+const DIRECTIVE_LOCATIONS: &[&str] = &[
+    // executable
+    "QUERY", "MUTATION", "SUBSCRIPTION", "FIELD", "FRAGMENT_DEFINITION", "FRAGMENT_SPREAD",
+    "INLINE_FRAGMENT", "VARIABLE_DEFINITION",
+    // type system
+    "SCHEMA", "SCALAR", "OBJECT", "FIELD_DEFINITION", "ARGUMENT_DEFINITION", "INTERFACE", "UNION",
+    "ENUM", "ENUM_VALUE", "INPUT_OBJECT", "INPUT_FIELD_DEFINITION"
+];
+
+/// The nesting depth `ParseOptions::new` allows.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// The number of tokens `ParseOptions::new` allows.
+pub const DEFAULT_MAX_TOKENS: usize = 100_000;
+
 #[derive(Clone, Copy)]
 pub struct ParseOptions {
     no_source:   bool,
-    no_location: bool
+    no_location: bool,
+    experimental_fragment_variables: bool,
+    max_tokens:        Option<usize>,
+    max_depth:         Option<usize>,
+    max_source_length: Option<usize>
 }
 
 impl ParseOptions {
+    /// The default options, which limit documents to `DEFAULT_MAX_DEPTH`
+    /// levels of nesting and `DEFAULT_MAX_TOKENS` tokens so that untrusted
+    /// input can't exhaust the stack or tie up the parser.
     pub fn new() -> ParseOptions {
         ParseOptions {
             no_source:   false,
-            no_location: false
+            no_location: false,
+            experimental_fragment_variables: false,
+            max_tokens:        Some(DEFAULT_MAX_TOKENS),
+            max_depth:         Some(DEFAULT_MAX_DEPTH),
+            max_source_length: None
         }
     }
 
     pub fn no_source() -> ParseOptions {
         ParseOptions {
             no_source:   true,
-            no_location: false
+            ..ParseOptions::new()
         }
     }
 
     pub fn no_location() -> ParseOptions {
         ParseOptions {
-            no_source:   false,
-            no_location: true
+            no_location: true,
+            ..ParseOptions::new()
         }
     }
 
@@ -57,551 +86,780 @@ impl ParseOptions {
     pub fn set_source(&mut self, source: bool) {
         self.no_source = source;
     }
-}
 
-pub struct Parser {
-    lex_token: Lexer,
-    source:    Source,
-    options:   ParseOptions,
-    prev_end:  usize,
-    token:     Token
-}
+    /// Allows fragments to declare variables, as in
+    /// `fragment F($id: ID) on T { ... }`. This syntax is not part of the
+    /// GraphQL specification and may change.
+    pub fn set_experimental_fragment_variables(&mut self, fragment_variables: bool) {
+        self.experimental_fragment_variables = fragment_variables;
+    }
 
-impl Parser {
-    pub fn parse(source: Source, options: ParseOptions) -> Result<Document, ParseError> {
-        let mut lexer = Lexer::lex(source.clone());
-        let token = lexer.next(None);
-        let parser = RwLock::new(Parser {
-            lex_token: lexer,
-            source: source,
-            options: options,
-            prev_end: 0,
-            token: token
-        });
-        InternalParser::parse(parser)
+    /// Fails the parse once the lexer has produced more than `max_tokens`
+    /// tokens, not counting EOF. `None` removes the limit.
+    pub fn set_max_tokens(&mut self, max_tokens: Option<usize>) {
+        self.max_tokens = max_tokens;
     }
-}
 
-type RwParser = RwLock<Parser>;
+    /// Fails the parse when selection sets, list and object values or list
+    /// types nest deeper than `max_depth`. The parser is recursive, so
+    /// untrusted input should always be parsed with a depth limit; only
+    /// remove it with `None` for trusted documents.
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) {
+        self.max_depth = max_depth;
+    }
 
-struct InternalParser {
-    parser: RwParser
+    /// Refuses to parse a source body longer than `max_source_length` bytes.
+    pub fn set_max_source_length(&mut self, max_source_length: Option<usize>) {
+        self.max_source_length = max_source_length;
+    }
 }
 
-impl InternalParser {
-    fn parse(parser: RwParser) -> Result<Document, ParseError> {
-        let ip = InternalParser {
-            parser: parser
-        };
-        ip.parse_document()
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions::new()
     }
+}
 
-    // Helpers
+/// A single-threaded recursive descent parser.
+///
+/// The parser owns its lexer and keeps exactly one token of lookahead in
+/// `token`. Helpers that consume a token hand it back by value, so token
+/// values are moved into the AST rather than cloned.
+pub struct Parser {
+    lexer:    Lexer,
+    source:   Source,
+    options:  ParseOptions,
+    prev_end: usize,
+    token:    Token,
+    tokens:   usize,
+    depth:    usize
+}
 
-    fn start(&self) -> usize {
-        self.parser.read().unwrap().token.start
+impl Parser {
+    pub fn parse(source: Source, options: ParseOptions) -> Result<Document, ParseError> {
+        Parser::run(source, options, Parser::parse_document)
     }
 
-    fn token_kind(&self) -> TokenKind {
-        self.parser.read().unwrap().token.kind
+    /// Parses several sources, e.g. one per file, into a single document.
+    ///
+    /// The combined document has no location of its own, but the locations
+    /// of its definitions point into the source each one was parsed from.
+    pub fn parse_all<I>(sources: I, options: ParseOptions) -> Result<Document, ParseError>
+        where I: IntoIterator<Item = Source> {
+        let mut definitions = vec![];
+        for source in sources {
+            definitions.extend(Parser::parse(source, options)?.definitions);
+        }
+        Ok(Document {
+            kind: Kinds::Document,
+            loc: None,
+            definitions
+        })
     }
 
-    fn options(&self) -> ParseOptions {
-        self.parser.read().unwrap().options
+    /// Parses a single value literal such as `[1, { a: $b }]`, which may
+    /// reference variables.
+    pub fn parse_value(source: Source, options: ParseOptions) -> Result<Value, ParseError> {
+        Parser::run(source, options, |parser| {
+            let value = parser.parse_value_literal(false)?;
+            parser.expect(TokenKind::EOF)?;
+            Ok(value)
+        })
     }
 
-    fn prev_end(&self) -> usize {
-        self.parser.read().unwrap().prev_end
+    /// Parses a single constant value literal, rejecting variables.
+    pub fn parse_const_value(source: Source, options: ParseOptions) -> Result<Value, ParseError> {
+        Parser::run(source, options, |parser| {
+            let value = parser.parse_value_literal(true)?;
+            parser.expect(TokenKind::EOF)?;
+            Ok(value)
+        })
     }
 
-    fn source_clone(&self) -> Source {
-        self.parser.read().unwrap().source.clone()
+    /// Parses a type reference such as `[String!]!`.
+    pub fn parse_type(source: Source, options: ParseOptions) -> Result<Type, ParseError> {
+        Parser::run(source, options, |parser| {
+            let _type = parser.parse_type_reference()?;
+            parser.expect(TokenKind::EOF)?;
+            Ok(_type)
+        })
     }
 
-    fn token_clone(&self) -> Token {
-        self.parser.read().unwrap().token.clone()
+    /// Runs `parse` over `source`, naming the source in any error and
+    /// locating it at the token the parser stopped on.
+    fn run<T, F>(source: Source, options: ParseOptions, parse: F) -> Result<T, ParseError>
+        where F: FnOnce(&mut Parser) -> Result<T, ParseError> {
+        let name = source.name().map(|name| name.to_owned());
+        Parser::new(source, options)
+            .and_then(|mut parser| parse(&mut parser).map_err(|mut error| {
+                if error.location.is_none() {
+                    error.location = Some(parser.source.location(parser.token.start));
+                }
+                error
+            }))
+            .map_err(|mut error| {
+                error.source_name = name;
+                error
+            })
+    }
+
+    fn new(source: Source, options: ParseOptions) -> Result<Parser, ParseError> {
+        if let Some(max) = options.max_source_length {
+            if source.body().len() > max {
+                return parse_error!("Document is {} bytes long, exceeding the limit of {} bytes", source.body().len(), max);
+            }
+        }
+
+        let mut parser = Parser {
+            lexer: Lexer::lex(source.clone()),
+            source,
+            options,
+            prev_end: 0,
+            token: Token { kind: TokenKind::EOF, start: 0, end: 0, value: None },
+            tokens: 0,
+            depth: 0
+        };
+        parser.advance()?;
+        parser.prev_end = 0;
+        Ok(parser)
     }
 
     // Parsers
 
-    fn parse_document(&self) -> Result<Document, ParseError> {
-        let start = self.start();
+    fn parse_document(&mut self) -> Result<Document, ParseError> {
+        let start = self.token.start;
         let mut definitions = vec![];
 
         // rust doesn't have do/while so we make our own
-        let mut cont = true;
-        while cont {
-            let definition = self.parse_definition(start);
-            let mut ok_definition;
-
-            if definition.is_ok() {
-                ok_definition = definition.ok().unwrap();
-            } else {
-                return Err(definition.err().unwrap());
+        loop {
+            definitions.push(self.parse_definition()?);
+            if self.skip(TokenKind::EOF)? {
+                break;
             }
-            definitions.push(ok_definition);
-            cont = !self.skip(TokenKind::EOF);
         }
 
         Ok(Document {
             kind: Kinds::Document,
             loc: self.loc(start),
-            definitions: definitions
+            definitions
         })
     }
 
-    fn parse_definition(&self, start: usize) -> Result<Definition, ParseError> {
+    fn parse_definition(&mut self) -> Result<Definition, ParseError> {
         if self.peek(TokenKind::BraceL) {
             self.parse_operation_definition()
         } else if self.peek(TokenKind::Name) {
-            let name_kind = { self.parser.read().unwrap().token.name_kind_from_value() };
-            match name_kind {
-                Some(s) => {
-                    match s {
-                        NameKind::Query | NameKind::Mutation => { self.parse_operation_definition() },
-                        NameKind::Fragment => { self.parse_fragment_definition() }
-                    }
-                },
-                None => {
-                    parse_error!("Could not parse document, missing NameKind at location {:?}", start)
-                }
+            match self.token.name_kind_from_value() {
+                Some(NameKind::Query) | Some(NameKind::Mutation) | Some(NameKind::Subscription) => self.parse_operation_definition(),
+                Some(NameKind::Fragment) => self.parse_fragment_definition(),
+                Some(NameKind::Directive) => self.parse_directive_definition(),
+                None => parse_error!("Could not parse document, missing NameKind")
             }
+        } else if self.peek_description() {
+            self.parse_directive_definition()
         } else {
-            parse_error!("Expected a BraceL or a Name at location {:?}", start)
+            parse_error!("Expected a BraceL or a Name")
         }
     }
 
-    fn parse_operation_definition(&self) -> Result<Definition, ParseError> {
-        let start = self.start();
+    fn parse_operation_definition(&mut self) -> Result<Definition, ParseError> {
+        let start = self.token.start;
         if self.peek(TokenKind::BraceL) {
-            Ok(Definition::Operation {
+            let selection_set = self.parse_selection_set()?;
+            return Ok(Definition::Operation {
                 kind: Kinds::OperationDefinition,
-                operation: "query".to_string(),
+                operation: OperationType::Query,
                 name: None,
                 variable_definitions: None,
                 directives: vec![],
-                selection_set: self.parse_selection_set(),
+                selection_set,
                 loc: self.loc(start)
-            })
+            });
+        }
+
+        let operation = self.parse_operation_type()?;
+        let name = if self.peek(TokenKind::Name) {
+            Some(self.parse_name()?)
         } else {
-            match self.expect(TokenKind::Name) {
-                Ok(ot) => {
-                    let operation = ot.value;
-                    Ok(Definition::Operation {
-                        kind: Kinds::OperationDefinition,
-                        operation: operation.unwrap(),
-                        name: Some(self.parse_name()),
-                        variable_definitions: Some(self.parse_variable_definitions()),
-                        directives: self.parse_directives(),
-                        selection_set: self.parse_selection_set(),
-                        loc: self.loc(start)
-                    })
-                },
-                Err(e) => Err(e)
-            }
+            None
+        };
+        let variable_definitions = self.parse_variable_definitions()?;
+        let directives = self.parse_directives(false)?;
+        let selection_set = self.parse_selection_set()?;
+        Ok(Definition::Operation {
+            kind: Kinds::OperationDefinition,
+            operation,
+            name,
+            variable_definitions: Some(variable_definitions),
+            directives,
+            selection_set,
+            loc: self.loc(start)
+        })
+    }
+
+    fn parse_operation_type(&mut self) -> Result<OperationType, ParseError> {
+        let operation = match self.token.name_kind_from_value() {
+            Some(NameKind::Query)        => OperationType::Query,
+            Some(NameKind::Mutation)     => OperationType::Mutation,
+            Some(NameKind::Subscription) => OperationType::Subscription,
+            _ => return self.unexpected()
+        };
+        self.expect(TokenKind::Name)?;
+        Ok(operation)
+    }
+
+    fn parse_fragment_definition(&mut self) -> Result<Definition, ParseError> {
+        let start = self.token.start;
+        self.expect_keyword("fragment")?;
+        let name = self.parse_fragment_name()?;
+        let variable_definitions = if self.options.experimental_fragment_variables && self.peek(TokenKind::ParenL) {
+            Some(self.parse_variable_definitions()?)
+        } else {
+            None
+        };
+        self.expect_keyword("on")?;
+        let type_condition = self.parse_named_type()?;
+        let directives = self.parse_directives(false)?;
+        let selection_set = self.parse_selection_set()?;
+        Ok(Definition::Fragment {
+            kind: Kinds::FragmentDefinition,
+            name,
+            variable_definitions,
+            type_condition,
+            directives: Some(directives),
+            selection_set,
+            loc: self.loc(start)
+        })
+    }
+
+    /// ```text
+    /// DirectiveDefinition :
+    ///   Description? directive @ Name ArgumentsDefinition? repeatable? on DirectiveLocations
+    /// ```
+    fn parse_directive_definition(&mut self) -> Result<Definition, ParseError> {
+        let start = self.token.start;
+        let description = self.parse_description()?;
+        self.expect_keyword("directive")?;
+        self.expect(TokenKind::At)?;
+        let name = self.parse_name()?;
+        let arguments = if self.peek(TokenKind::ParenL) {
+            self.many(TokenKind::ParenL, Parser::parse_input_value_definition, TokenKind::ParenR)?
+        } else {
+            vec![]
+        };
+        let repeatable = self.peek_keyword("repeatable");
+        if repeatable {
+            self.advance()?;
+        }
+        self.expect_keyword("on")?;
+        let locations = self.parse_directive_locations()?;
+        Ok(Definition::Directive {
+            kind: Kinds::DirectiveDefinition,
+            description,
+            name,
+            arguments,
+            repeatable,
+            locations,
+            loc: self.loc(start)
+        })
+    }
+
+    fn parse_directive_locations(&mut self) -> Result<Vec<Name>, ParseError> {
+        // a leading pipe is allowed
+        self.skip(TokenKind::Pipe)?;
+        let mut locations = vec![self.parse_directive_location()?];
+        while self.skip(TokenKind::Pipe)? {
+            locations.push(self.parse_directive_location()?);
         }
+        Ok(locations)
     }
 
-    fn parse_fragment_definition(&self) -> Result<Definition, ParseError> {
-        let start = self.start();
-        match self.expect_keyword("fragment") {
-            Ok(_) => {
-                let name = self.parse_fragment_name();
-                let type_condition = match self.expect_keyword("on") {
-                    Ok(_)  => Ok(self.parse_named_type()),
-                    Err(e) => Err(e)
-                };
-                match type_condition {
-                    Ok(tc) => Ok(Definition::Fragment {
-                        kind: Kinds::FragmentDefinition,
-                        name: name,
-                        type_condition: tc,
-                        directives: Some(self.parse_directives()),
-                        selection_set: self.parse_selection_set(),
-                        loc: self.loc(start)
-                    }),
-                    Err(e) => Err(e)
-                }
-            },
-            Err(e) => Err(e)
+    fn parse_directive_location(&mut self) -> Result<Name, ParseError> {
+        let known = match self.token.value {
+            Some(ref value) => self.token.kind == TokenKind::Name && DIRECTIVE_LOCATIONS.contains(&value.as_str()),
+            None            => false
+        };
+        if !known {
+            return self.unexpected();
+        }
+        self.parse_name()
+    }
+
+    fn parse_input_value_definition(&mut self) -> Result<InputValueDefinition, ParseError> {
+        let start = self.token.start;
+        let description = self.parse_description()?;
+        let name = self.parse_name()?;
+        self.expect(TokenKind::Colon)?;
+        let value_type = self.parse_type_reference()?;
+        let default_value = if self.skip(TokenKind::Equals)? {
+            Some(self.parse_value_literal(true)?)
+        } else {
+            None
+        };
+        let directives = self.parse_directives(true)?;
+        Ok(InputValueDefinition {
+            kind: Kinds::InputValueDefinition,
+            description,
+            name,
+            value_type,
+            default_value,
+            directives,
+            loc: self.loc(start)
+        })
+    }
+
+    fn parse_description(&mut self) -> Result<Option<Value>, ParseError> {
+        if self.peek_description() {
+            Ok(Some(self.parse_value_literal(true)?))
+        } else {
+            Ok(None)
         }
     }
 
-    fn parse_selection_set(&self) -> SelectionSet {
-        let start = self.start();
-        SelectionSet {
+    fn parse_selection_set(&mut self) -> Result<SelectionSet, ParseError> {
+        let start = self.token.start;
+        self.enter()?;
+        let selections = self.many(TokenKind::BraceL, Parser::parse_selection, TokenKind::BraceR)?;
+        self.leave();
+        Ok(SelectionSet {
             kind: Kinds::SelectionSet,
-            selections: self.many(TokenKind::BraceL, || -> Selection {
-                if self.peek(TokenKind::Spread) {
-                    self.parse_fragment()
-                } else {
-                    self.parse_field()
-                }
-            }, TokenKind::BraceR),
+            selections,
             loc: self.loc(start)
+        })
+    }
+
+    fn parse_selection(&mut self) -> Result<Selection, ParseError> {
+        if self.peek(TokenKind::Spread) {
+            self.parse_fragment()
+        } else {
+            self.parse_field()
         }
     }
 
-    fn parse_fragment(&self) -> Selection {
-        let start = self.start();
-        let _ = self.expect(TokenKind::Spread);
-        let token = self.token_clone();
-        let value = token.clone().value.unwrap_or("".to_string());
-        if value == "on".to_string() {
-            self.advance();
-            Selection::InlineFragment {
+    fn parse_fragment(&mut self) -> Result<Selection, ParseError> {
+        let start = self.token.start;
+        self.expect(TokenKind::Spread)?;
+        if self.peek_keyword("on") || self.peek(TokenKind::At) || self.peek(TokenKind::BraceL) {
+            let type_condition = if self.peek_keyword("on") {
+                self.advance()?;
+                Some(self.parse_named_type()?)
+            } else {
+                None
+            };
+            let directives = self.parse_directives(false)?;
+            let selection_set = self.parse_selection_set()?;
+            Ok(Selection::InlineFragment {
                 kind: Kinds::InlineFragment,
-                type_condition: self.parse_named_type(),
-                directives: Some(self.parse_directives()),
-                selection_set: self.parse_selection_set(),
+                type_condition,
+                directives: Some(directives),
+                selection_set,
                 loc: self.loc(start)
-            }
+            })
         } else {
-            Selection::FragmentSpread {
+            let name = self.parse_fragment_name()?;
+            let directives = self.parse_directives(false)?;
+            Ok(Selection::FragmentSpread {
                 kind: Kinds::FragmentSpread,
-                name: self.parse_fragment_name(),
-                directives: Some(self.parse_directives()),
+                name,
+                directives: Some(directives),
                 loc: self.loc(start),
-            }
+            })
         }
     }
 
-    fn parse_field(&self) -> Selection {
-        let start = self.start();
-        let name_or_alias = self.parse_name();
-        let mut alias;
-        let mut name;
-        if self.skip(TokenKind::Colon) {
-            alias = Some(name_or_alias);
-            name  = self.parse_name();
+    fn parse_field(&mut self) -> Result<Selection, ParseError> {
+        let start = self.token.start;
+        let name_or_alias = self.parse_name()?;
+        let (alias, name) = if self.skip(TokenKind::Colon)? {
+            (Some(name_or_alias), self.parse_name()?)
         } else {
-            alias = None;
-            name  = name_or_alias;
-        }
+            (None, name_or_alias)
+        };
 
-        Selection::Field {
+        let arguments = self.parse_arguments(false)?;
+        let directives = self.parse_directives(false)?;
+        let selection_set = if self.peek(TokenKind::BraceL) {
+            Some(self.parse_selection_set()?)
+        } else {
+            None
+        };
+
+        Ok(Selection::Field {
             kind: Kinds::Field,
-            alias: alias,
-            name: name,
-            arguments: self.parse_arguments(),
-            directives: self.parse_directives(),
-            selection_set: if self.peek(TokenKind::BraceL) {
-                Some(self.parse_selection_set())
-            } else {
-                None
-            },
+            alias,
+            name,
+            arguments,
+            directives,
+            selection_set,
             loc: self.loc(start)
-        }
+        })
     }
 
-    fn parse_name(&self) -> Name {
-        match self.expect(TokenKind::Name) {
-            Ok(token) => {
-                Name {
-                    kind: Kinds::Name,
-                    value: token.value.unwrap(),
-                    loc: self.loc(token.start)
-                }
-            },
-            _ => { panic!("expected Name, found... something else") }
-        }
+    fn parse_name(&mut self) -> Result<Name, ParseError> {
+        let token = self.expect(TokenKind::Name)?;
+        Ok(Name {
+            kind: Kinds::Name,
+            value: token.value.unwrap_or_default(),
+            loc: self.loc(token.start)
+        })
     }
 
-    fn parse_arguments(&self) -> Vec<Argument> {
+    fn parse_arguments(&mut self, is_const: bool) -> Result<Vec<Argument>, ParseError> {
         if self.peek(TokenKind::ParenL) {
-            self.many(TokenKind::ParenL, || -> Argument {
-                let start = self.start();
-                Argument {
-                    kind: Kinds::Argument,
-                    name: self.parse_name(),
-                    value: { let _ = self.expect(TokenKind::Colon); self.parse_value(false) },
-                    loc: self.loc(start)
-                }
-            }, TokenKind::ParenR)
+            self.many(TokenKind::ParenL, |parser| parser.parse_argument(is_const), TokenKind::ParenR)
         } else {
-            vec![]
+            Ok(vec![])
         }
     }
 
-    fn parse_variable_definitions(&self) -> Vec<VariableDefinition> {
+    fn parse_argument(&mut self, is_const: bool) -> Result<Argument, ParseError> {
+        let start = self.token.start;
+        let name = self.parse_name()?;
+        self.expect(TokenKind::Colon)?;
+        let value = self.parse_value_literal(is_const)?;
+        Ok(Argument {
+            kind: Kinds::Argument,
+            name,
+            value,
+            loc: self.loc(start)
+        })
+    }
+
+    fn parse_variable_definitions(&mut self) -> Result<Vec<VariableDefinition>, ParseError> {
         if self.peek(TokenKind::ParenL) {
-            self.many(TokenKind::ParenL, || -> VariableDefinition {
-                let start = self.start();
-                VariableDefinition {
-                    kind: Kinds::VariableDefinition,
-                    variable: self.parse_variable(),
-                    var_type: { let _ = self.expect(TokenKind::Colon); self.parse_type() },
-                    default_value: match self.skip(TokenKind::Equals) {
-                        true => Some(self.parse_value(true)),
-                        false => None
-                    },
-                    loc: self.loc(start)
-                }
-            }, TokenKind::ParenR)
+            self.many(TokenKind::ParenL, Parser::parse_variable_definition, TokenKind::ParenR)
         } else {
-            vec![]
+            Ok(vec![])
         }
     }
 
-    fn parse_directives(&self) -> Vec<Directive> {
+    fn parse_variable_definition(&mut self) -> Result<VariableDefinition, ParseError> {
+        let start = self.token.start;
+        let variable = self.parse_variable()?;
+        self.expect(TokenKind::Colon)?;
+        let var_type = self.parse_type_reference()?;
+        let default_value = if self.skip(TokenKind::Equals)? {
+            Some(self.parse_value_literal(true)?)
+        } else {
+            None
+        };
+        let directives = self.parse_directives(true)?;
+        Ok(VariableDefinition {
+            kind: Kinds::VariableDefinition,
+            variable,
+            var_type,
+            default_value,
+            directives,
+            loc: self.loc(start)
+        })
+    }
+
+    fn parse_directives(&mut self, is_const: bool) -> Result<Vec<Directive>, ParseError> {
         let mut directives = vec![];
         while self.peek(TokenKind::At) {
-            directives.push(self.parse_directive());
+            directives.push(self.parse_directive(is_const)?);
         }
-        return directives;
+        Ok(directives)
     }
 
-    fn parse_fragment_name(&self) -> Name {
-        let token = self.token_clone();
-        let value = token.clone().value.unwrap_or("".to_string());
-        if value == "on".to_string() {
-            panic!("not supposed ot be 'on'");
+    fn parse_fragment_name(&mut self) -> Result<Name, ParseError> {
+        if self.peek_keyword("on") {
+            return parse_error!("Unexpected Name 'on'");
         }
         self.parse_name()
     }
 
-    fn parse_named_type(&self) -> Type {
-        let start = self.start();
-        Type::Named {
+    fn parse_named_type(&mut self) -> Result<Type, ParseError> {
+        let start = self.token.start;
+        let name = self.parse_name()?;
+        Ok(Type::Named {
             kind: Kinds::NamedType,
-            name: self.parse_name(),
+            name,
             loc: self.loc(start)
-        }
+        })
     }
 
-    fn parse_value(&self, is_const: bool) -> Value {
-        let token = self.token_clone();
-        let value = token.value;
-        match token.kind {
+    fn parse_value_literal(&mut self, is_const: bool) -> Result<Value, ParseError> {
+        match self.token.kind {
             TokenKind::BracketL => self.parse_array(is_const),
             TokenKind::BraceL   => self.parse_object(is_const),
-            TokenKind::Int      => {
-                self.advance();
-                Value::IntValue {
+            TokenKind::Dollar if is_const => parse_error!("Unexpected variable in constant value"),
+            TokenKind::Dollar => self.parse_variable(),
+            TokenKind::Int => {
+                let token = self.advance()?;
+                Ok(Value::IntValue {
                     kind: Kinds::Int,
-                    value: value.clone().unwrap(),
+                    value: token.value.unwrap_or_default(),
                     loc: self.loc(token.start)
-                }
+                })
             },
             TokenKind::Float => {
-                self.advance();
-                Value::FloatValue {
+                let token = self.advance()?;
+                Ok(Value::FloatValue {
                     kind: Kinds::Float,
-                    value: value.clone().unwrap(),
+                    value: token.value.unwrap_or_default(),
                     loc: self.loc(token.start)
-                }
+                })
             },
-            TokenKind::String => {
-                self.advance();
-                Value::StringValue {
+            TokenKind::String | TokenKind::BlockString => {
+                let token = self.advance()?;
+                Ok(Value::StringValue {
                     kind: Kinds::String,
-                    value: value.clone().unwrap(),
+                    value: token.value.unwrap_or_default(),
+                    block: token.kind == TokenKind::BlockString,
                     loc: self.loc(token.start)
-                }
+                })
             },
-            TokenKind::Name  => {
-                if value.clone().unwrap_or("".to_string()) == "true".to_string() || value.clone().unwrap_or("".to_string()) == "false".to_string() {
-                    self.advance();
-                    return Value::BooleanValue {
-                        kind: Kinds::Boolean,
-                        value: value.clone().unwrap() == "true".to_string(),
-                        loc: self.loc(token.start)
-                    };
-                } else if value.clone().unwrap_or("".to_string()) != "null".to_string() {
-                    self.advance();
-                    return Value::EnumValue {
-                        kind: Kinds::Enum,
-                        value: value.clone().unwrap(),
-                        loc: self.loc(token.start)
-                    };
-                }
-                panic!("no value?");
+            TokenKind::Name if self.peek_keyword("true") || self.peek_keyword("false") => {
+                let token = self.advance()?;
+                Ok(Value::BooleanValue {
+                    kind: Kinds::Boolean,
+                    value: token.value.as_ref().is_some_and(|v| v == "true"),
+                    loc: self.loc(token.start)
+                })
             },
-            TokenKind::Dollar => {
-                match is_const {
-                    true  => panic!("no value?"),
-                    false => self.parse_variable()
-                }
-            }
-            _ => panic!("Unexpected token kind: {:?}", token.kind)
+            TokenKind::Name if self.peek_keyword("null") => {
+                let token = self.advance()?;
+                Ok(Value::NullValue {
+                    kind: Kinds::Null,
+                    loc: self.loc(token.start)
+                })
+            },
+            TokenKind::Name => {
+                let token = self.advance()?;
+                Ok(Value::EnumValue {
+                    kind: Kinds::Enum,
+                    value: token.value.unwrap_or_default(),
+                    loc: self.loc(token.start)
+                })
+            },
+            _ => self.unexpected()
         }
     }
 
-    fn parse_variable(&self) -> Value {
-        let start = self.start();
-        let _ = self.expect(TokenKind::Dollar);
-        Value::VariableValue {
+    fn parse_variable(&mut self) -> Result<Value, ParseError> {
+        let start = self.token.start;
+        self.expect(TokenKind::Dollar)?;
+        let name = self.parse_name()?;
+        Ok(Value::VariableValue {
             kind: Kinds::Variable,
-            name: self.parse_name(),
+            name,
             loc: self.loc(start)
-        }
+        })
     }
 
-    fn parse_type(&self) -> Type {
-        let start = self.start();
-        let mut _type;
+    fn parse_type_reference(&mut self) -> Result<Type, ParseError> {
+        let start = self.token.start;
 
-        if self.skip(TokenKind::BracketL) {
-            let temp_type = Box::new(self.parse_type());
-            let _ = self.expect(TokenKind::BracketR);
-            _type = Type::List {
+        let _type = if self.skip(TokenKind::BracketL)? {
+            self.enter()?;
+            let inner = self.parse_type_reference()?;
+            self.leave();
+            self.expect(TokenKind::BracketR)?;
+            Type::List {
                 kind: Kinds::ListType,
-                t_type: temp_type,
+                t_type: Box::new(inner),
                 loc: self.loc(start)
             }
         } else {
-            _type = self.parse_named_type();
-        }
+            self.parse_named_type()?
+        };
 
-        if self.skip(TokenKind::Bang) {
-            return Type::NonNull {
+        if self.skip(TokenKind::Bang)? {
+            return Ok(Type::NonNull {
                 kind: Kinds::NonNullType,
                 t_type: Box::new(_type),
                 loc: self.loc(start)
-            };
+            });
         }
 
-        return _type;
+        Ok(_type)
     }
 
-    fn parse_directive(&self) -> Directive {
-        let start = self.start();
-        let _ = self.expect(TokenKind::At);
-        Directive {
+    fn parse_directive(&mut self, is_const: bool) -> Result<Directive, ParseError> {
+        let start = self.token.start;
+        self.expect(TokenKind::At)?;
+        let name = self.parse_name()?;
+        let arguments = self.parse_arguments(is_const)?;
+        Ok(Directive {
             kind: Kinds::Directive,
-            name: self.parse_name(),
-            arguments: Some(self.parse_arguments()),
+            name,
+            arguments: Some(arguments),
             loc: self.loc(start)
-        }
+        })
     }
 
-    fn parse_array(&self, is_const: bool) -> Value {
-        let start = self.start();
-        Value::ArrayValue {
+    fn parse_array(&mut self, is_const: bool) -> Result<Value, ParseError> {
+        let start = self.token.start;
+        self.enter()?;
+        let values = self.any(TokenKind::BracketL, |parser| parser.parse_value_literal(is_const), TokenKind::BracketR)?;
+        self.leave();
+        Ok(Value::ArrayValue {
             kind: Kinds::Array,
-            values: self.any(TokenKind::BracketL, || -> Value {
-                self.parse_value(is_const)
-            }, TokenKind::BracketR),
+            values,
             loc: self.loc(start),
-        }
+        })
     }
 
-    fn parse_object(&self, is_const: bool) -> Value {
-        let start = self.start();
-        let _ = self.expect(TokenKind::BraceL);
-        let mut field_names : HashMap<String, bool> = HashMap::new();
+    fn parse_object(&mut self, is_const: bool) -> Result<Value, ParseError> {
+        let start = self.token.start;
+        self.expect(TokenKind::BraceL)?;
+        let mut field_names = HashSet::new();
         let mut fields = vec![];
-        while !self.skip(TokenKind::BraceR) {
-            fields.push(self.parse_object_field(is_const, &mut field_names));
+        self.enter()?;
+        while !self.skip(TokenKind::BraceR)? {
+            fields.push(self.parse_object_field(is_const, &mut field_names)?);
         }
-        Value::ObjectValue {
+        self.leave();
+        Ok(Value::ObjectValue {
             kind: Kinds::Object,
-            fields: fields,
+            fields,
             loc: self.loc(start)
-        }
+        })
     }
 
-    fn parse_object_field(&self, is_const: bool, field_names: &mut HashMap<String, bool>) -> ObjectField {
-        let start = self.start();
-        let parsed_name = self.parse_name();
-        let value = parsed_name.value.clone();
-        if field_names.contains_key(&value) {
-            panic!("Duplicate input object field {:?}", value);
+    fn parse_object_field(&mut self, is_const: bool, field_names: &mut HashSet<String>) -> Result<ObjectField, ParseError> {
+        let start = self.token.start;
+        let name = self.parse_name()?;
+        if !field_names.insert(name.value.clone()) {
+            return parse_error!("Duplicate input object field {}.", name.value);
         }
-        field_names.insert(value, true);
-        ObjectField {
+        self.expect(TokenKind::Colon)?;
+        let value = self.parse_value_literal(is_const)?;
+        Ok(ObjectField {
             kind: Kinds::ObjectField,
-            name: parsed_name,
-            value: { let _ = self.expect(TokenKind::Colon); self.parse_value(is_const) },
+            name,
+            value,
             loc: self.loc(start)
-        }
+        })
     }
 
 
     // Iteration
-    fn many<T, F>(&self, open_kind: TokenKind, parse_fn: F, close_kind: TokenKind) -> Vec<T>
-        where F : Fn() -> T {
-        let _ = self.expect(open_kind);
-        let mut nodes = vec![parse_fn()];
+    fn many<T, F>(&mut self, open_kind: TokenKind, mut parse_fn: F, close_kind: TokenKind) -> Result<Vec<T>, ParseError>
+        where F : FnMut(&mut Parser) -> Result<T, ParseError> {
+        self.expect(open_kind)?;
+        let mut nodes = vec![parse_fn(self)?];
 
-        while !self.skip(close_kind) {
-            nodes.push(parse_fn());
+        while !self.skip(close_kind)? {
+            nodes.push(parse_fn(self)?);
         }
 
-        return nodes;
+        Ok(nodes)
     }
 
-    fn any<T, F>(&self, open_kind: TokenKind, parse_fn: F, close_kind: TokenKind) -> Vec<T>
-        where F : Fn() -> T {
-        let _ = self.expect(open_kind);
+    fn any<T, F>(&mut self, open_kind: TokenKind, mut parse_fn: F, close_kind: TokenKind) -> Result<Vec<T>, ParseError>
+        where F : FnMut(&mut Parser) -> Result<T, ParseError> {
+        self.expect(open_kind)?;
         let mut nodes = vec![];
 
-        while !self.skip(close_kind) {
-            nodes.push(parse_fn());
+        while !self.skip(close_kind)? {
+            nodes.push(parse_fn(self)?);
         }
 
-        return nodes;
+        Ok(nodes)
     }
 
     // Introspection
     fn loc(&self, start: usize) -> Option<Location> {
-        if self.options().no_location {
+        if self.options.no_location {
             None
-        } else if self.options().no_source {
+        } else if self.options.no_source {
             Some(Location {
-                start: start,
-                end: self.prev_end(),
+                start,
+                end: self.prev_end,
                 source: None
             })
         } else {
             Some(Location {
-                start: start,
-                end: self.prev_end(),
-                source: Some(self.source_clone())
+                start,
+                end: self.prev_end,
+                source: Some(self.source.clone())
             })
         }
     }
-    
+
     fn peek(&self, kind: TokenKind) -> bool {
-        self.parser.read().unwrap().token.kind == kind
+        self.token.kind == kind
     }
 
-    fn skip(&self, kind: TokenKind) -> bool {
-        match self.token_kind() == kind {
-            true => {
-                self.advance();
-                true
-            }
-            _ => false
+    fn peek_description(&self) -> bool {
+        self.peek(TokenKind::String) || self.peek(TokenKind::BlockString)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.token.kind == TokenKind::Name && self.token.value.as_ref().is_some_and(|v| v == keyword)
+    }
+
+    fn skip(&mut self, kind: TokenKind) -> Result<bool, ParseError> {
+        if self.peek(kind) {
+            self.advance()?;
+            Ok(true)
+        } else {
+            Ok(false)
         }
     }
 
-    fn expect_keyword(&self, keyword: &str) -> Result<Token, ParseError> { 
-        let token = self.token_clone();
-        let value = token.value.clone().unwrap_or("".to_string());
-        if token.kind == TokenKind::Name && value == keyword.to_string() {
-            self.advance();
-            return Ok(token);
+    fn expect_keyword(&mut self, keyword: &str) -> Result<Token, ParseError> {
+        if self.peek_keyword(keyword) {
+            return self.advance();
         }
 
-        parse_error!("Expected '{}' and got '{}'", keyword, value)
+        parse_error!("Expected '{}' and got '{}'", keyword, self.token.value.as_ref().map_or("", |v| v.as_str()))
     }
 
-    fn expect(&self, kind: TokenKind) -> Result<Token, ParseError> {
-        let token = self.token_clone();
-        if token.kind == kind {
-            self.advance();
-            return Ok(token);
+    fn expect(&mut self, kind: TokenKind) -> Result<Token, ParseError> {
+        if self.peek(kind) {
+            return self.advance();
         }
 
-        parse_error!("Expected {:?}, found {:?}", kind, token.kind)
+        parse_error!("Expected {:?}, found {:?}", kind, self.token.kind)
     }
 
-    fn advance(&self) {
-        let mut parser = self.parser.write().unwrap();
-        let prev_end = parser.token.end;
-        parser.prev_end = prev_end;
-        parser.token = parser.lex_token.next(None);
+    fn unexpected<T>(&self) -> Result<T, ParseError> {
+        match self.token.value {
+            Some(ref value) => parse_error!("Unexpected {:?} '{}'", self.token.kind, value),
+            None            => parse_error!("Unexpected {:?}", self.token.kind)
+        }
+    }
+
+    // Limits
+
+    fn enter(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        match self.options.max_depth {
+            Some(max) if self.depth > max => parse_error!("Document exceeds the maximum nesting depth of {}", max),
+            _ => Ok(())
+        }
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Moves to the next token, returning the one that was current.
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        let next = match self.lexer.next() {
+            Some(Ok(next)) => next,
+            Some(Err(error)) => {
+                let location = self.source.location(error.position);
+                let mut error = ParseError::from(error);
+                error.location = Some(location);
+                return Err(error);
+            },
+            None       => Token { kind: TokenKind::EOF, start: self.token.end, end: self.token.end, value: None }
+        };
+        if next.kind != TokenKind::EOF {
+            self.tokens += 1;
+            if let Some(max) = self.options.max_tokens {
+                if self.tokens > max {
+                    return parse_error!("Document contains more than {} tokens", max);
+                }
+            }
+        }
+        let token = mem::replace(&mut self.token, next);
+        self.prev_end = token.end;
+        Ok(token)
     }
 }
 
@@ -612,22 +870,22 @@ mod tests {
     #[test]
     fn default_parse_options() {
         let po = ParseOptions::new();
-        assert_eq!(po.no_location, false);
-        assert_eq!(po.no_source, false);
+        assert!(!po.no_location);
+        assert!(!po.no_source);
     }
 
     #[test]
     fn no_location_parse_options() {
         let po = ParseOptions::no_location();
-        assert_eq!(po.no_location, true);
-        assert_eq!(po.no_source, false);
+        assert!(po.no_location);
+        assert!(!po.no_source);
     }
 
     #[test]
     fn no_source_parse_options() {
         let po = ParseOptions::no_source();
-        assert_eq!(po.no_source, true);
-        assert_eq!(po.no_location, false);
+        assert!(po.no_source);
+        assert!(!po.no_location);
     }
 
     #[test]
@@ -635,7 +893,29 @@ mod tests {
         let mut po = ParseOptions::new();
         po.set_location(true);
         po.set_source(true);
-        assert_eq!(po.no_source, true);
-        assert_eq!(po.no_location, true);
+        assert!(po.no_source);
+        assert!(po.no_location);
+    }
+
+    #[test]
+    fn experimental_fragment_variables_parse_option() {
+        let mut po = ParseOptions::new();
+        assert!(!po.experimental_fragment_variables);
+        po.set_experimental_fragment_variables(true);
+        assert!(po.experimental_fragment_variables);
+    }
+
+    #[test]
+    fn limit_parse_options() {
+        let mut po = ParseOptions::new();
+        assert_eq!(po.max_tokens, Some(DEFAULT_MAX_TOKENS));
+        assert_eq!(po.max_depth, Some(DEFAULT_MAX_DEPTH));
+        assert_eq!(po.max_source_length, None);
+        po.set_max_tokens(Some(10));
+        po.set_max_depth(Some(20));
+        po.set_max_source_length(Some(30));
+        assert_eq!(po.max_tokens, Some(10));
+        assert_eq!(po.max_depth, Some(20));
+        assert_eq!(po.max_source_length, Some(30));
     }
 }
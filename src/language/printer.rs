@@ -0,0 +1,441 @@
+use crate::language::ast::{
+    Document,
+    Definition,
+    OperationType,
+    VariableDefinition,
+    InputValueDefinition,
+    SelectionSet,
+    Selection,
+    Argument,
+    Directive,
+    Type,
+    Value,
+    ObjectField
+};
+use crate::language::errors::LexError;
+use crate::language::lexer::{Source, Lexer, TokenKind};
+
+/// Prints `document` as canonical GraphQL source, indented with two spaces.
+pub fn print(document: &Document) -> String {
+    let mut printer = Printer::new();
+    printer.print_document(document);
+    printer.out
+}
+
+/// Prints `document` with no insignificant whitespace, commas or comments.
+/// The output is deterministic, so it can be hashed to identify a query.
+pub fn print_compact(document: &Document) -> String {
+    let mut printer = Printer::new();
+    printer.compact = true;
+    printer.print_document(document);
+    printer.out
+}
+
+/// Prints `selection` the way `print_compact` does inside a document.
+pub(crate) fn print_compact_selection(selection: &Selection) -> String {
+    let mut printer = Printer::new();
+    printer.compact = true;
+    printer.print_selection(selection);
+    printer.out
+}
+
+/// Strips every ignored token from `source`, keeping only the spaces needed
+/// to separate adjacent names and numbers. Strings are reprinted from their
+/// values, so equivalent escapes come out the same.
+pub fn minify(source: Source) -> Result<String, LexError> {
+    let mut printer = Printer::new();
+    printer.compact = true;
+    for token in Lexer::lex(source.clone()) {
+        let token = token?;
+        match token.kind {
+            TokenKind::EOF => break,
+            TokenKind::String | TokenKind::BlockString => {
+                let value = token.value.unwrap_or_default();
+                if token.kind == TokenKind::String {
+                    printer.print_string(&value);
+                } else {
+                    printer.print_block_string(&value);
+                }
+            },
+            _ => printer.write(&source.body()[token.start..token.end])
+        }
+    }
+    Ok(printer.out)
+}
+
+pub fn print_value(value: &Value) -> String {
+    let mut printer = Printer::new();
+    printer.print_value(value);
+    printer.out
+}
+
+pub fn print_type(t_type: &Type) -> String {
+    let mut printer = Printer::new();
+    printer.print_type(t_type);
+    printer.out
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+    compact: bool
+}
+
+impl Printer {
+    fn new() -> Printer {
+        Printer { out: String::new(), indent: 0, compact: false }
+    }
+
+    /// Writes `text`, with a space before it if printing compactly would
+    /// otherwise run it into the previous name, number or string.
+    fn write(&mut self, text: &str) {
+        if self.compact {
+            let word = |c: char| c.is_alphanumeric() || c == '_';
+            if let (Some(last), Some(next)) = (self.out.chars().last(), text.chars().next()) {
+                if (word(last) && word(next)) || (last == '"' && next == '"') {
+                    self.out.push(' ');
+                }
+            }
+        }
+        self.out.push_str(text);
+    }
+
+    /// Writes a space that only matters for readability.
+    fn space(&mut self) {
+        if !self.compact {
+            self.out.push(' ');
+        }
+    }
+
+    fn newline(&mut self) {
+        if self.compact {
+            return;
+        }
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+    }
+
+    /// Writes `items` separated by `separator`, or nothing if there are none.
+    /// Printing compactly drops the spaces and commas from the separator.
+    fn join<T, F>(&mut self, items: &[T], separator: &str, mut print: F) where F: FnMut(&mut Printer, &T) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                if self.compact {
+                    self.write(separator.trim_matches(|c| c == ' ' || c == ','));
+                } else {
+                    self.out.push_str(separator);
+                }
+            }
+            print(self, item);
+        }
+    }
+
+    /// Writes `(items)`, or nothing if there are none.
+    fn wrap<T, F>(&mut self, items: &[T], print: F) where F: FnMut(&mut Printer, &T) {
+        if !items.is_empty() {
+            self.out.push('(');
+            self.join(items, ", ", print);
+            self.out.push(')');
+        }
+    }
+
+    fn print_document(&mut self, document: &Document) {
+        for (i, definition) in document.definitions.iter().enumerate() {
+            if i > 0 && !self.compact {
+                self.out.push_str("\n\n");
+            }
+            self.print_definition(definition);
+        }
+    }
+
+    fn print_definition(&mut self, definition: &Definition) {
+        match *definition {
+            Definition::Operation { operation, ref name, ref variable_definitions, ref directives, ref selection_set, .. } => {
+                let variable_definitions = variable_definitions.as_ref().map_or(&[][..], |v| &v[..]);
+                let shorthand = operation == OperationType::Query && name.is_none()
+                    && variable_definitions.is_empty() && directives.is_empty();
+                if !shorthand {
+                    self.write(&operation.to_string());
+                    match *name {
+                        Some(ref name) => {
+                            self.space();
+                            self.write(&name.value);
+                        },
+                        None if !variable_definitions.is_empty() => self.space(),
+                        None => {}
+                    }
+                    self.wrap(variable_definitions, Printer::print_variable_definition);
+                    self.print_directives(directives);
+                    self.space();
+                }
+                self.print_selection_set(selection_set);
+            },
+            Definition::Fragment { ref name, ref variable_definitions, ref type_condition, ref directives, ref selection_set, .. } => {
+                self.write("fragment");
+                self.space();
+                self.write(&name.value);
+                if let Some(ref variable_definitions) = *variable_definitions {
+                    self.wrap(variable_definitions, Printer::print_variable_definition);
+                }
+                self.space();
+                self.write("on");
+                self.space();
+                self.print_type(type_condition);
+                if let Some(ref directives) = *directives {
+                    self.print_directives(directives);
+                }
+                self.space();
+                self.print_selection_set(selection_set);
+            },
+            Definition::Directive { ref description, ref name, ref arguments, repeatable, ref locations, .. } => {
+                self.print_description(description);
+                self.write("directive");
+                self.space();
+                self.out.push('@');
+                self.out.push_str(&name.value);
+                if arguments.iter().any(|a| a.description.is_some()) {
+                    self.out.push('(');
+                    self.indent += 1;
+                    for argument in arguments {
+                        self.newline();
+                        self.print_input_value_definition(argument);
+                    }
+                    self.indent -= 1;
+                    self.newline();
+                    self.out.push(')');
+                } else {
+                    self.wrap(arguments, Printer::print_input_value_definition);
+                }
+                if repeatable {
+                    self.space();
+                    self.write("repeatable");
+                }
+                self.space();
+                self.write("on");
+                self.space();
+                self.join(locations, " | ", |p, location| p.write(&location.value));
+            }
+        }
+    }
+
+    fn print_description(&mut self, description: &Option<Value>) {
+        if let Some(ref description) = *description {
+            self.print_value(description);
+            self.newline();
+        }
+    }
+
+    fn print_variable_definition(&mut self, definition: &VariableDefinition) {
+        self.print_value(&definition.variable);
+        self.out.push(':');
+        self.space();
+        self.print_type(&definition.var_type);
+        if let Some(ref default_value) = definition.default_value {
+            self.space();
+            self.out.push('=');
+            self.space();
+            self.print_value(default_value);
+        }
+        self.print_directives(&definition.directives);
+    }
+
+    fn print_input_value_definition(&mut self, definition: &InputValueDefinition) {
+        self.print_description(&definition.description);
+        self.write(&definition.name.value);
+        self.out.push(':');
+        self.space();
+        self.print_type(&definition.value_type);
+        if let Some(ref default_value) = definition.default_value {
+            self.space();
+            self.out.push('=');
+            self.space();
+            self.print_value(default_value);
+        }
+        self.print_directives(&definition.directives);
+    }
+
+    fn print_selection_set(&mut self, selection_set: &SelectionSet) {
+        self.out.push('{');
+        self.indent += 1;
+        for selection in &selection_set.selections {
+            self.newline();
+            self.print_selection(selection);
+        }
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+
+    fn print_selection(&mut self, selection: &Selection) {
+        match *selection {
+            Selection::Field { ref alias, ref name, ref arguments, ref directives, ref selection_set, .. } => {
+                if let Some(ref alias) = *alias {
+                    self.write(&alias.value);
+                    self.out.push(':');
+                    self.space();
+                }
+                self.write(&name.value);
+                self.wrap(arguments, Printer::print_argument);
+                self.print_directives(directives);
+                if let Some(ref selection_set) = *selection_set {
+                    self.space();
+                    self.print_selection_set(selection_set);
+                }
+            },
+            Selection::FragmentSpread { ref name, ref directives, .. } => {
+                self.out.push_str("...");
+                self.write(&name.value);
+                if let Some(ref directives) = *directives {
+                    self.print_directives(directives);
+                }
+            },
+            Selection::InlineFragment { ref type_condition, ref directives, ref selection_set, .. } => {
+                self.out.push_str("...");
+                if let Some(ref type_condition) = *type_condition {
+                    self.space();
+                    self.write("on");
+                    self.space();
+                    self.print_type(type_condition);
+                }
+                if let Some(ref directives) = *directives {
+                    self.print_directives(directives);
+                }
+                self.space();
+                self.print_selection_set(selection_set);
+            }
+        }
+    }
+
+    fn print_argument(&mut self, argument: &Argument) {
+        self.write(&argument.name.value);
+        self.out.push(':');
+        self.space();
+        self.print_value(&argument.value);
+    }
+
+    fn print_directives(&mut self, directives: &[Directive]) {
+        for directive in directives {
+            self.space();
+            self.out.push('@');
+            self.out.push_str(&directive.name.value);
+            if let Some(ref arguments) = directive.arguments {
+                self.wrap(arguments, Printer::print_argument);
+            }
+        }
+    }
+
+    fn print_type(&mut self, t_type: &Type) {
+        match *t_type {
+            Type::Named { ref name, .. } => self.write(&name.value),
+            Type::List { ref t_type, .. } => {
+                self.out.push('[');
+                self.print_type(t_type);
+                self.out.push(']');
+            },
+            Type::NonNull { ref t_type, .. } => {
+                self.print_type(t_type);
+                self.out.push('!');
+            }
+        }
+    }
+
+    fn print_value(&mut self, value: &Value) {
+        match *value {
+            Value::IntValue { ref value, .. } |
+            Value::FloatValue { ref value, .. } |
+            Value::EnumValue { ref value, .. } => self.write(value),
+            Value::StringValue { ref value, block: true, .. } => self.print_block_string(value),
+            Value::StringValue { ref value, block: false, .. } => self.print_string(value),
+            Value::BooleanValue { value, .. } => self.write(if value { "true" } else { "false" }),
+            Value::NullValue { .. } => self.write("null"),
+            Value::ArrayValue { ref values, .. } => {
+                self.out.push('[');
+                self.join(values, ", ", Printer::print_value);
+                self.out.push(']');
+            },
+            Value::ObjectValue { ref fields, .. } => {
+                self.out.push('{');
+                self.join(fields, ", ", Printer::print_object_field);
+                self.out.push('}');
+            },
+            Value::VariableValue { ref name, .. } => {
+                self.out.push('$');
+                self.out.push_str(&name.value);
+            }
+        }
+    }
+
+    fn print_object_field(&mut self, field: &ObjectField) {
+        self.write(&field.name.value);
+        self.out.push(':');
+        self.space();
+        self.print_value(&field.value);
+    }
+
+    fn print_string(&mut self, value: &str) {
+        self.write("\"");
+        for c in value.chars() {
+            match c {
+                '"'  => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\u{8}' => self.out.push_str("\\b"),
+                '\u{c}' => self.out.push_str("\\f"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\u{0}'..='\u{1f}' | '\u{7f}'..='\u{9f}' => {
+                    self.out.push_str(&format!("\\u{:04X}", c as u32));
+                },
+                c => self.out.push(c)
+            }
+        }
+        self.out.push('"');
+    }
+
+    /// Prints `value` so that it reads back as the same block string value
+    /// once the common indentation is stripped again. Compact printing only
+    /// breaks lines where the value itself needs it.
+    fn print_block_string(&mut self, value: &str) {
+        let escaped = value.replace("\"\"\"", "\\\"\"\"");
+        let lines: Vec<&str> = escaped.split('\n').collect();
+        let starts_with_whitespace = |line: &str| line.starts_with(' ') || line.starts_with('\t');
+
+        let single_line = lines.len() == 1;
+        let force_leading_newline = lines.len() > 1 && lines[1..].iter().all(|line| line.is_empty() || starts_with_whitespace(line));
+        let trailing_triple_quotes = escaped.ends_with("\\\"\"\"");
+        let force_trailing_newline = (value.ends_with('"') && !trailing_triple_quotes) || value.ends_with('\\');
+        let multiple_lines = !self.compact && (!single_line || value.len() > 70 || force_trailing_newline
+            || force_leading_newline || trailing_triple_quotes);
+
+        self.write("\"\"\"");
+        if (multiple_lines && !(single_line && starts_with_whitespace(value))) || force_leading_newline {
+            self.line_break();
+        }
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                if line.is_empty() {
+                    self.out.push('\n');
+                } else {
+                    self.line_break();
+                }
+            }
+            self.out.push_str(line);
+        }
+        if multiple_lines || force_trailing_newline {
+            self.line_break();
+        }
+        self.out.push_str("\"\"\"");
+    }
+
+    /// Breaks a line inside a block string, where the break is part of
+    /// the value even when printing compactly.
+    fn line_break(&mut self) {
+        if self.compact {
+            self.out.push('\n');
+        } else {
+            self.newline();
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use crate::language::ast::{
+    Document,
+    Definition,
+    VariableDefinition,
+    InputValueDefinition,
+    SelectionSet,
+    Selection,
+    Argument,
+    Directive,
+    Name,
+    Type,
+    Value,
+    ObjectField
+};
+use crate::language::kinds::Kinds;
+
+use std::mem;
+
+/// A borrowed reference to any node of a `Document`.
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    Document(&'a Document),
+    Definition(&'a Definition),
+    VariableDefinition(&'a VariableDefinition),
+    InputValueDefinition(&'a InputValueDefinition),
+    SelectionSet(&'a SelectionSet),
+    Selection(&'a Selection),
+    Argument(&'a Argument),
+    Directive(&'a Directive),
+    Name(&'a Name),
+    Type(&'a Type),
+    Value(&'a Value),
+    ObjectField(&'a ObjectField)
+}
+
+impl<'a> Node<'a> {
+    pub fn kind(&self) -> Kinds {
+        match *self {
+            Node::Document(node)             => node.kind,
+            Node::Definition(node)           => match *node {
+                Definition::Operation { kind, .. } |
+                Definition::Fragment { kind, .. } |
+                Definition::Directive { kind, .. } => kind
+            },
+            Node::VariableDefinition(node)   => node.kind,
+            Node::InputValueDefinition(node) => node.kind,
+            Node::SelectionSet(node)         => node.kind,
+            Node::Selection(node)            => match *node {
+                Selection::Field { kind, .. } |
+                Selection::FragmentSpread { kind, .. } |
+                Selection::InlineFragment { kind, .. } => kind
+            },
+            Node::Argument(node)             => node.kind,
+            Node::Directive(node)            => node.kind,
+            Node::Name(node)                 => node.kind,
+            Node::Type(node)                 => match *node {
+                Type::Named { kind, .. } |
+                Type::List { kind, .. } |
+                Type::NonNull { kind, .. } => kind
+            },
+            Node::Value(node)                => match *node {
+                Value::IntValue { kind, .. } |
+                Value::FloatValue { kind, .. } |
+                Value::StringValue { kind, .. } |
+                Value::BooleanValue { kind, .. } |
+                Value::NullValue { kind, .. } |
+                Value::EnumValue { kind, .. } |
+                Value::ArrayValue { kind, .. } |
+                Value::ObjectValue { kind, .. } |
+                Value::VariableValue { kind, .. } => kind
+            },
+            Node::ObjectField(node)          => node.kind
+        }
+    }
+
+    /// The direct children of this node, in source order.
+    pub fn children(&self) -> Vec<Node<'a>> {
+        let mut children = vec![];
+        match *self {
+            Node::Document(node) => {
+                children.extend(node.definitions.iter().map(Node::Definition));
+            },
+            Node::Definition(Definition::Operation { name, variable_definitions, directives, selection_set, .. }) => {
+                children.extend(name.iter().map(Node::Name));
+                children.extend(variable_definitions.iter().flat_map(|v| v.iter()).map(Node::VariableDefinition));
+                children.extend(directives.iter().map(Node::Directive));
+                children.push(Node::SelectionSet(selection_set));
+            },
+            Node::Definition(Definition::Fragment { name, variable_definitions, type_condition, directives, selection_set, .. }) => {
+                children.push(Node::Name(name));
+                children.extend(variable_definitions.iter().flat_map(|v| v.iter()).map(Node::VariableDefinition));
+                children.push(Node::Type(type_condition));
+                children.extend(directives.iter().flat_map(|d| d.iter()).map(Node::Directive));
+                children.push(Node::SelectionSet(selection_set));
+            },
+            Node::Definition(Definition::Directive { description, name, arguments, locations, .. }) => {
+                children.extend(description.iter().map(Node::Value));
+                children.push(Node::Name(name));
+                children.extend(arguments.iter().map(Node::InputValueDefinition));
+                children.extend(locations.iter().map(Node::Name));
+            },
+            Node::VariableDefinition(node) => {
+                children.push(Node::Value(&node.variable));
+                children.push(Node::Type(&node.var_type));
+                children.extend(node.default_value.iter().map(Node::Value));
+                children.extend(node.directives.iter().map(Node::Directive));
+            },
+            Node::InputValueDefinition(node) => {
+                children.extend(node.description.iter().map(Node::Value));
+                children.push(Node::Name(&node.name));
+                children.push(Node::Type(&node.value_type));
+                children.extend(node.default_value.iter().map(Node::Value));
+                children.extend(node.directives.iter().map(Node::Directive));
+            },
+            Node::SelectionSet(node) => {
+                children.extend(node.selections.iter().map(Node::Selection));
+            },
+            Node::Selection(Selection::Field { alias, name, arguments, directives, selection_set, .. }) => {
+                children.extend(alias.iter().map(Node::Name));
+                children.push(Node::Name(name));
+                children.extend(arguments.iter().map(Node::Argument));
+                children.extend(directives.iter().map(Node::Directive));
+                children.extend(selection_set.iter().map(Node::SelectionSet));
+            },
+            Node::Selection(Selection::FragmentSpread { name, directives, .. }) => {
+                children.push(Node::Name(name));
+                children.extend(directives.iter().flat_map(|d| d.iter()).map(Node::Directive));
+            },
+            Node::Selection(Selection::InlineFragment { type_condition, directives, selection_set, .. }) => {
+                children.extend(type_condition.iter().map(Node::Type));
+                children.extend(directives.iter().flat_map(|d| d.iter()).map(Node::Directive));
+                children.push(Node::SelectionSet(selection_set));
+            },
+            Node::Argument(node) => {
+                children.push(Node::Name(&node.name));
+                children.push(Node::Value(&node.value));
+            },
+            Node::Directive(node) => {
+                children.push(Node::Name(&node.name));
+                children.extend(node.arguments.iter().flat_map(|a| a.iter()).map(Node::Argument));
+            },
+            Node::Name(_) => {},
+            Node::Type(Type::Named { name, .. }) => {
+                children.push(Node::Name(name));
+            },
+            Node::Type(Type::List { t_type, .. }) |
+            Node::Type(Type::NonNull { t_type, .. }) => {
+                children.push(Node::Type(t_type));
+            },
+            Node::Value(Value::ArrayValue { values, .. }) => {
+                children.extend(values.iter().map(Node::Value));
+            },
+            Node::Value(Value::ObjectValue { fields, .. }) => {
+                children.extend(fields.iter().map(Node::ObjectField));
+            },
+            Node::Value(Value::VariableValue { name, .. }) => {
+                children.push(Node::Name(name));
+            },
+            Node::Value(_) => {},
+            Node::ObjectField(node) => {
+                children.push(Node::Name(&node.name));
+                children.push(Node::Value(&node.value));
+            }
+        }
+        children
+    }
+
+    /// Whether both references point at the very same node.
+    pub fn same(&self, other: &Node) -> bool {
+        mem::discriminant(self) == mem::discriminant(other) && self.address() == other.address()
+    }
+
+    fn address(&self) -> *const () {
+        match *self {
+            Node::Document(node)             => node as *const Document as *const (),
+            Node::Definition(node)           => node as *const Definition as *const (),
+            Node::VariableDefinition(node)   => node as *const VariableDefinition as *const (),
+            Node::InputValueDefinition(node) => node as *const InputValueDefinition as *const (),
+            Node::SelectionSet(node)         => node as *const SelectionSet as *const (),
+            Node::Selection(node)            => node as *const Selection as *const (),
+            Node::Argument(node)             => node as *const Argument as *const (),
+            Node::Directive(node)            => node as *const Directive as *const (),
+            Node::Name(node)                 => node as *const Name as *const (),
+            Node::Type(node)                 => node as *const Type as *const (),
+            Node::Value(node)                => node as *const Value as *const (),
+            Node::ObjectField(node)          => node as *const ObjectField as *const ()
+        }
+    }
+}
+
+/// What a `Visitor` wants the traversal to do next.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum VisitAction {
+    /// Keep going.
+    Continue,
+    /// Returned from `enter`: don't visit this node's children, and don't
+    /// call `leave` for it.
+    Skip,
+    /// Stop the traversal.
+    Break
+}
+
+/// Hooks called while walking a document depth first.
+///
+/// Match on `node.kind()` (or on the `Node` itself) to react to
+/// particular kinds of nodes.
+pub trait Visitor<'a> {
+    fn enter(&mut self, _node: Node<'a>) -> VisitAction {
+        VisitAction::Continue
+    }
+
+    fn leave(&mut self, _node: Node<'a>) -> VisitAction {
+        VisitAction::Continue
+    }
+}
+
+/// Walks `document`, calling `visitor` on entering and leaving every node.
+pub fn visit<'a, V: Visitor<'a> + ?Sized>(document: &'a Document, visitor: &mut V) {
+    walk(Node::Document(document), visitor);
+}
+
+/// Walks the subtree rooted at `node`. Returns false if the visitor broke
+/// out of the traversal.
+pub fn walk<'a, V: Visitor<'a> + ?Sized>(node: Node<'a>, visitor: &mut V) -> bool {
+    match visitor.enter(node) {
+        VisitAction::Break    => return false,
+        VisitAction::Skip     => return true,
+        VisitAction::Continue => {}
+    }
+
+    for child in node.children() {
+        if !walk(child, visitor) {
+            return false;
+        }
+    }
+
+    visitor.leave(node) != VisitAction::Break
+}
+
+type Hook<'a, 'f> = Box<dyn FnMut(Node<'a>) -> VisitAction + 'f>;
+
+/// A visitor built from closures registered per node kind, e.g.
+/// `visitor.on_enter(Kinds::Field, |node| ...)`.
+#[derive(Default)]
+pub struct KindVisitor<'a, 'f> {
+    enter: Vec<(Kinds, Hook<'a, 'f>)>,
+    leave: Vec<(Kinds, Hook<'a, 'f>)>
+}
+
+impl<'a, 'f> KindVisitor<'a, 'f> {
+    pub fn new() -> KindVisitor<'a, 'f> {
+        KindVisitor {
+            enter: vec![],
+            leave: vec![]
+        }
+    }
+
+    pub fn on_enter<F>(&mut self, kind: Kinds, hook: F) where F: FnMut(Node<'a>) -> VisitAction + 'f {
+        self.enter.push((kind, Box::new(hook)));
+    }
+
+    pub fn on_leave<F>(&mut self, kind: Kinds, hook: F) where F: FnMut(Node<'a>) -> VisitAction + 'f {
+        self.leave.push((kind, Box::new(hook)));
+    }
+
+    fn dispatch(hooks: &mut [(Kinds, Hook<'a, 'f>)], node: Node<'a>) -> VisitAction {
+        let kind = node.kind();
+        for &mut (hook_kind, ref mut hook) in hooks.iter_mut() {
+            if hook_kind == kind {
+                let action = hook(node);
+                if action != VisitAction::Continue {
+                    return action;
+                }
+            }
+        }
+        VisitAction::Continue
+    }
+}
+
+impl<'a, 'f> Visitor<'a> for KindVisitor<'a, 'f> {
+    fn enter(&mut self, node: Node<'a>) -> VisitAction {
+        KindVisitor::dispatch(&mut self.enter, node)
+    }
+
+    fn leave(&mut self, node: Node<'a>) -> VisitAction {
+        KindVisitor::dispatch(&mut self.leave, node)
+    }
+}
+
+enum Skipping<'a> {
+    No,
+    Subtree(Node<'a>),
+    Done
+}
+
+/// Runs several visitors over a document in a single traversal.
+///
+/// Each visitor skips subtrees and breaks independently of the others; the
+/// traversal only stops once every visitor has broken.
+pub struct ParallelVisitor<'v, 'a: 'v> {
+    visitors: Vec<&'v mut dyn Visitor<'a>>,
+    skipping: Vec<Skipping<'a>>
+}
+
+impl<'v, 'a> ParallelVisitor<'v, 'a> {
+    pub fn new(visitors: Vec<&'v mut dyn Visitor<'a>>) -> ParallelVisitor<'v, 'a> {
+        let skipping = visitors.iter().map(|_| Skipping::No).collect();
+        ParallelVisitor {
+            visitors,
+            skipping
+        }
+    }
+
+    fn all_done(&self) -> bool {
+        self.skipping.iter().all(|s| matches!(*s, Skipping::Done))
+    }
+}
+
+impl<'v, 'a> Visitor<'a> for ParallelVisitor<'v, 'a> {
+    fn enter(&mut self, node: Node<'a>) -> VisitAction {
+        for (visitor, skipping) in self.visitors.iter_mut().zip(self.skipping.iter_mut()) {
+            if let Skipping::No = *skipping {
+                match visitor.enter(node) {
+                    VisitAction::Continue => {},
+                    VisitAction::Skip     => *skipping = Skipping::Subtree(node),
+                    VisitAction::Break    => *skipping = Skipping::Done
+                }
+            }
+        }
+
+        if self.all_done() { VisitAction::Break } else { VisitAction::Continue }
+    }
+
+    fn leave(&mut self, node: Node<'a>) -> VisitAction {
+        for (visitor, skipping) in self.visitors.iter_mut().zip(self.skipping.iter_mut()) {
+            let action = match *skipping {
+                Skipping::No => visitor.leave(node),
+                Skipping::Subtree(ref skipped) if skipped.same(&node) => VisitAction::Continue,
+                _ => continue
+            };
+            *skipping = match action {
+                VisitAction::Break => Skipping::Done,
+                _                  => Skipping::No
+            };
+        }
+
+        if self.all_done() { VisitAction::Break } else { VisitAction::Continue }
+    }
+}
@@ -1,28 +1,42 @@
-#[macro_use]
 extern crate log;
 extern crate env_logger;
+#[macro_use]
+extern crate wrapped_enum;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
 pub mod language;
 pub mod types;
 pub mod executor;
+pub mod errors;
+pub mod request;
+pub mod value;
 
-use types::definition::Schema;
-use language::lexer::Source;
-use language::parser::{Parser, ParseOptions};
-use executor::Executor;
+use crate::types::definition::Schema;
+use crate::language::lexer::Source;
+use crate::language::parser::{Parser, ParseOptions};
+use crate::executor::{Executor, block_on};
+use crate::request::{Request, Response};
 
 pub struct GraphQL;
 
 impl GraphQL {
-    pub fn query(schema: Schema, query: String) -> String {
-        let source = Source::from(query);
-        let ast    = Parser::parse(source, ParseOptions::new());
-        if ast.is_ok() {
+    /// Parses and executes `request` against `schema`, waiting for any
+    /// asynchronous resolvers on the current thread.
+    pub fn execute(schema: &Schema, request: &Request) -> Response {
+        block_on(GraphQL::execute_async(schema, request))
+    }
+
+    /// Like `execute`, but returns a future to await from whichever runtime
+    /// the resolvers need.
+    pub async fn execute_async(schema: &Schema, request: &Request) -> Response {
+        let source = Source::new(&request.query);
+        match Parser::parse(source, ParseOptions::new()) {
             // TODO validate
-            let _ = Executor::execute(schema, ast.ok().unwrap());
-            String::from("Foo")
-        } else {
-            panic!("FUCK!");
+            Ok(document) => Executor::execute_async(schema, &document, request).await,
+            Err(error) => Response::from_error(error.into())
         }
     }
 }
@@ -0,0 +1,136 @@
+use crate::errors::GraphQLError;
+use crate::value::{Value, Map};
+use crate::value::json::{self, JsonError};
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// Variable values by name, without the `$`.
+pub type Variables = HashMap<String, Value>;
+
+/// Everything needed to execute a query against a schema.
+pub struct Request {
+    pub query: String,
+    /// Which operation to run when the query contains several.
+    pub operation_name: Option<String>,
+    pub variables: Variables,
+    pub context: Context,
+    /// The value root fields are resolved against.
+    pub root_value: Value
+}
+
+impl Request {
+    pub fn new(query: &str) -> Request {
+        Request {
+            query: query.to_owned(),
+            operation_name: None,
+            variables: HashMap::new(),
+            context: Context::new(),
+            root_value: Value::Null
+        }
+    }
+
+    /// Reads a request from the usual JSON body of a GraphQL HTTP POST,
+    /// `{"query": "...", "operationName": "...", "variables": {...}}`.
+    pub fn from_json(body: &str) -> Result<Request, JsonError> {
+        let invalid = |description: &str, position| JsonError { description: description.to_owned(), position };
+        // Like an object value, a repeated key keeps its last value.
+        let mut query = None;
+        let mut operation_name = None;
+        let mut variables = None;
+        for (key, position, value) in json::fields_from_str(body)? {
+            match &key[..] {
+                "query" => query = Some((position, value)),
+                "operationName" => operation_name = Some((position, value)),
+                "variables" => variables = Some((position, value)),
+                _ => {}
+            }
+        }
+
+        let mut request = match query {
+            Some((_, Value::String(query))) => Request::new(&query),
+            Some((position, _)) => return Err(invalid("Expected \"query\" to be a string", position)),
+            None => return Err(invalid("Expected \"query\" to be a string", 0))
+        };
+        match operation_name {
+            Some((_, Value::String(name))) => request.operation_name = Some(name),
+            None | Some((_, Value::Null)) => {},
+            Some((position, _)) => return Err(invalid("Expected \"operationName\" to be a string", position))
+        }
+        match variables {
+            Some((_, Value::Object(variables))) => request.variables = variables.into_iter().collect(),
+            None | Some((_, Value::Null)) => {},
+            Some((position, _)) => return Err(invalid("Expected \"variables\" to be an object", position))
+        }
+        Ok(request)
+    }
+}
+
+/// Application state handed to every resolver, e.g. a database handle or the
+/// current user.
+#[derive(Default)]
+pub struct Context {
+    value: Option<Box<dyn Any + Send + Sync>>
+}
+
+impl Context {
+    pub fn new() -> Context {
+        Context { value: None }
+    }
+
+    pub fn set<T: Any + Send + Sync>(&mut self, value: T) {
+        self.value = Some(Box::new(value));
+    }
+
+    /// The context value, if one of type `T` was set.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.value.as_ref().and_then(|value| value.downcast_ref())
+    }
+}
+
+/// The result of executing a request.
+#[derive(PartialEq, Debug)]
+pub struct Response {
+    /// `None` if the request failed before execution started, e.g. because
+    /// the query didn't parse.
+    pub data: Option<Value>,
+    pub errors: Vec<GraphQLError>,
+    pub extensions: Option<Value>
+}
+
+impl Response {
+    pub fn from_error(error: GraphQLError) -> Response {
+        Response {
+            data: None,
+            errors: vec![error],
+            extensions: None
+        }
+    }
+
+    /// The response in its serialized shape. Empty `errors` and missing
+    /// `data` and `extensions` are left out.
+    pub fn to_value(&self) -> Value {
+        let mut fields = Map::new();
+        if !self.errors.is_empty() {
+            fields.insert("errors".to_owned(), Value::List(self.errors.iter().map(GraphQLError::to_value).collect()));
+        }
+        if let Some(ref data) = self.data {
+            fields.insert("data".to_owned(), data.clone());
+        }
+        if let Some(ref extensions) = self.extensions {
+            fields.insert("extensions".to_owned(), extensions.clone());
+        }
+        Value::Object(fields)
+    }
+
+    /// Writes the response as JSON, streaming it rather than building the
+    /// whole string first.
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        json::write_response(writer, self)
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_value().to_json()
+    }
+}
@@ -1,7 +1,379 @@
+use crate::errors::FieldError;
+use crate::executor::{ResolveInfo, BoxFuture};
+use crate::language::ast;
+use crate::request::Context;
+use crate::value::Value;
+
 use std::collections::HashMap;
+use std::fmt;
+use std::future;
+
+pub type FieldResult = Result<Value, FieldError>;
+
+/// Produces a field's value from its parent value, arguments and context.
+pub type Resolver = Box<dyn Fn(&ResolveInfo) -> FieldResult + Send + Sync>;
+
+/// A field's value that may not be ready yet.
+pub type FieldFuture<'a> = BoxFuture<'a, FieldResult>;
+
+/// Like `Resolver`, for fields whose values come from I/O.
+pub type AsyncResolver = Box<dyn for<'a> Fn(&'a ResolveInfo<'a>) -> FieldFuture<'a> + Send + Sync>;
+
+/// Names the object type of a value of an interface or union type.
+pub type TypeResolver = Box<dyn Fn(&Value, &Context) -> Option<String> + Send + Sync>;
+
+/// Tells whether a value belongs to an object type.
+pub type IsTypeOf = Box<dyn Fn(&Value, &Context) -> bool + Send + Sync>;
 
 pub struct Schema {
-    pub query: Object
+    query: String,
+    mutation: Option<String>,
+    types: HashMap<String, Type>
+}
+
+impl Schema {
+    /// A schema with `query` as its query root type. The built-in scalars
+    /// are always defined; every other type the schema uses has to be added
+    /// with `add_type`.
+    pub fn new(query: Object) -> Schema {
+        let mut schema = Schema {
+            query: query.name.clone(),
+            mutation: None,
+            types: HashMap::new()
+        };
+        for scalar in [Scalar::int(), Scalar::float(), Scalar::string(), Scalar::boolean(), Scalar::id()] {
+            schema.add_type(scalar);
+        }
+        schema.add_type(query);
+        schema
+    }
+
+    pub fn add_type<T: Into<Type>>(&mut self, named_type: T) {
+        let named_type = named_type.into();
+        self.types.insert(named_type.name().to_owned(), named_type);
+    }
+
+    pub fn get_type(&self, name: &str) -> Option<&Type> {
+        self.types.get(name)
+    }
+
+    pub fn query_type(&self) -> &Type {
+        &self.types[&self.query]
+    }
+
+    /// Makes `mutation` the mutation root type. Without one, mutations are
+    /// rejected.
+    pub fn set_mutation(&mut self, mutation: Object) {
+        self.mutation = Some(mutation.name.clone());
+        self.add_type(mutation);
+    }
+
+    pub fn mutation_type(&self) -> Option<&Type> {
+        self.mutation.as_ref().map(|name| &self.types[name])
+    }
+
+    /// The object types a value of `abstract_type` can have: the
+    /// implementations of an interface or the members of a union.
+    pub fn possible_types<'a>(&'a self, abstract_type: &'a Type) -> Vec<&'a Object> {
+        let mut objects: Vec<&Object> = match *abstract_type {
+            Type::Interface(ref interface) => self.types.values().filter_map(|named_type| match *named_type {
+                Type::Object(ref object) if object.interfaces.contains(&interface.name) => Some(object),
+                _ => None
+            }).collect(),
+            Type::Union(ref union) => union.types.iter().filter_map(|name| match self.types.get(name) {
+                Some(Type::Object(object)) => Some(object),
+                _ => None
+            }).collect(),
+            Type::Object(ref object) => vec![object],
+            _ => vec![]
+        };
+        if let Type::Interface(_) = *abstract_type {
+            objects.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        objects
+    }
+
+    /// Whether `object` is, implements or belongs to `named_type`.
+    pub fn is_possible_type(&self, named_type: &Type, object: &Object) -> bool {
+        match *named_type {
+            Type::Object(ref other) => other.name == object.name,
+            Type::Interface(ref interface) => object.interfaces.contains(&interface.name),
+            Type::Union(ref union) => union.types.contains(&object.name),
+            _ => false
+        }
+    }
+}
+
+wrapped_enum!{
+    /// A named type in a schema.
+    pub enum Type {
+        /// A leaf value such as an `Int` or a custom scalar
+        Scalar(Scalar),
+        /// A leaf value from a fixed set of names
+        Enum(Enum),
+        /// A concrete type with fields
+        Object(Object),
+        /// An abstract type with fields its implementations share
+        Interface(Interface),
+        /// An abstract type that is one of a set of object types
+        Union(Union),
+        /// A structured value accepted as an argument or variable
+        InputObject(InputObject)
+    }
+}
+
+impl Type {
+    pub fn name(&self) -> &str {
+        match *self {
+            Type::Scalar(ref scalar)       => &scalar.name,
+            Type::Enum(ref enum_type)      => &enum_type.name,
+            Type::Object(ref object)       => &object.name,
+            Type::Interface(ref interface) => &interface.name,
+            Type::Union(ref union)         => &union.name,
+            Type::InputObject(ref input)   => &input.name
+        }
+    }
+
+    /// Whether values of this type can be given as arguments or variables.
+    pub fn is_input_type(&self) -> bool {
+        match *self {
+            Type::Scalar(_) | Type::Enum(_) | Type::InputObject(_) => true,
+            Type::Object(_) | Type::Interface(_) | Type::Union(_) => false
+        }
+    }
+
+    /// The object type name of `value` according to the type's
+    /// `resolve_type`, for interfaces and unions that have one.
+    pub fn resolve_type(&self, value: &Value, context: &Context) -> Option<Option<String>> {
+        let resolve_type = match *self {
+            Type::Interface(ref interface) => interface.resolve_type.as_ref(),
+            Type::Union(ref union) => union.resolve_type.as_ref(),
+            _ => None
+        };
+        resolve_type.map(|resolve_type| resolve_type(value, context))
+    }
+
+    /// The field called `name`, for types that have fields.
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        let fields = match *self {
+            Type::Object(ref object)       => &object.fields,
+            Type::Interface(ref interface) => &interface.fields,
+            _ => return None
+        };
+        fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// A reference to a named type, possibly wrapped in lists and non-null.
+#[derive(PartialEq, Debug, Clone)]
+pub enum TypeRef {
+    Named(String),
+    List(Box<TypeRef>),
+    NonNull(Box<TypeRef>)
+}
+
+impl TypeRef {
+    pub fn named(name: &str) -> TypeRef {
+        TypeRef::Named(name.to_owned())
+    }
+
+    pub fn list(of: TypeRef) -> TypeRef {
+        TypeRef::List(Box::new(of))
+    }
+
+    pub fn non_null(of: TypeRef) -> TypeRef {
+        TypeRef::NonNull(Box::new(of))
+    }
+
+    /// The name of the type inside any wrappers.
+    pub fn name(&self) -> &str {
+        match *self {
+            TypeRef::Named(ref name) => name,
+            TypeRef::List(ref of) | TypeRef::NonNull(ref of) => of.name()
+        }
+    }
+}
+
+impl<'a> From<&'a ast::Type> for TypeRef {
+    fn from(t_type: &'a ast::Type) -> TypeRef {
+        match *t_type {
+            ast::Type::Named { ref name, .. }      => TypeRef::Named(name.value.clone()),
+            ast::Type::List { ref t_type, .. }     => TypeRef::list(t_type.as_ref().into()),
+            ast::Type::NonNull { ref t_type, .. }  => TypeRef::non_null(t_type.as_ref().into())
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TypeRef::Named(ref name) => write!(f, "{}", name),
+            TypeRef::List(ref of)    => write!(f, "[{}]", of),
+            TypeRef::NonNull(ref of) => write!(f, "{}!", of)
+        }
+    }
+}
+
+pub struct Scalar {
+    pub name: String,
+    serialize: fn(&Value) -> Option<Value>,
+    parse_value: fn(&Value) -> Option<Value>,
+    parse_literal: Option<fn(&ast::Value) -> Option<Value>>
+}
+
+impl Scalar {
+    /// A custom scalar. `serialize` turns a resolved value into its response
+    /// value, or returns `None` if the value isn't valid for the scalar.
+    /// Input values are accepted as they are until `set_parse_value` says
+    /// otherwise.
+    pub fn new(name: &str, serialize: fn(&Value) -> Option<Value>) -> Scalar {
+        Scalar {
+            name: name.to_owned(),
+            serialize,
+            parse_value: |value| Some(value.clone()),
+            parse_literal: None
+        }
+    }
+
+    /// Sets how variable and argument values are turned into the scalar's
+    /// internal value. Returning `None` rejects the value.
+    pub fn set_parse_value(&mut self, parse_value: fn(&Value) -> Option<Value>) {
+        self.parse_value = parse_value;
+    }
+
+    /// Sets how literals in a query are turned into the scalar's internal
+    /// value. Until this is set, a literal is read as the value it spells
+    /// and handed to `parse_value`.
+    pub fn set_parse_literal(&mut self, parse_literal: fn(&ast::Value) -> Option<Value>) {
+        self.parse_literal = Some(parse_literal);
+    }
+
+    pub fn int() -> Scalar {
+        let mut scalar = Scalar::new("Int", |value| match *value {
+            Value::Int(value) if value >= i64::from(i32::MIN) && value <= i64::from(i32::MAX) => Some(Value::Int(value)),
+            Value::Float(value) if value.fract() == 0.0 && value.abs() <= f64::from(i32::MAX) => Some(Value::Int(value as i64)),
+            Value::Boolean(value) => Some(Value::Int(value as i64)),
+            _ => None
+        });
+        scalar.set_parse_value(|value| match *value {
+            Value::Int(value) if value >= i64::from(i32::MIN) && value <= i64::from(i32::MAX) => Some(Value::Int(value)),
+            Value::Float(value) if value.fract() == 0.0 && value.abs() <= f64::from(i32::MAX) => Some(Value::Int(value as i64)),
+            _ => None
+        });
+        scalar.set_parse_literal(|value| match *value {
+            ast::Value::IntValue { ref value, .. } => value.parse::<i32>().ok().map(|value| Value::Int(i64::from(value))),
+            _ => None
+        });
+        scalar
+    }
+
+    pub fn float() -> Scalar {
+        let mut scalar = Scalar::new("Float", |value| match *value {
+            Value::Int(value) => Some(Value::Float(value as f64)),
+            Value::Float(value) if value.is_finite() => Some(Value::Float(value)),
+            Value::Boolean(value) => Some(Value::Float(if value { 1.0 } else { 0.0 })),
+            _ => None
+        });
+        scalar.set_parse_value(|value| match *value {
+            Value::Int(value) => Some(Value::Float(value as f64)),
+            Value::Float(value) if value.is_finite() => Some(Value::Float(value)),
+            _ => None
+        });
+        scalar.set_parse_literal(|value| match *value {
+            ast::Value::IntValue { ref value, .. } | ast::Value::FloatValue { ref value, .. } => {
+                value.parse::<f64>().ok().filter(|value| value.is_finite()).map(Value::Float)
+            },
+            _ => None
+        });
+        scalar
+    }
+
+    pub fn string() -> Scalar {
+        let mut scalar = Scalar::new("String", |value| match *value {
+            Value::String(_) => Some(value.clone()),
+            Value::Enum(ref value) => Some(Value::String(value.clone())),
+            Value::Int(value) => Some(Value::String(value.to_string())),
+            Value::Float(value) => Some(Value::String(value.to_string())),
+            Value::Boolean(value) => Some(Value::String(value.to_string())),
+            _ => None
+        });
+        scalar.set_parse_value(|value| match *value {
+            Value::String(_) => Some(value.clone()),
+            _ => None
+        });
+        scalar.set_parse_literal(|value| match *value {
+            ast::Value::StringValue { ref value, .. } => Some(Value::String(value.clone())),
+            _ => None
+        });
+        scalar
+    }
+
+    pub fn boolean() -> Scalar {
+        let mut scalar = Scalar::new("Boolean", |value| match *value {
+            Value::Boolean(_) => Some(value.clone()),
+            Value::Int(value) => Some(Value::Boolean(value != 0)),
+            Value::Float(value) => Some(Value::Boolean(value != 0.0)),
+            _ => None
+        });
+        scalar.set_parse_value(|value| match *value {
+            Value::Boolean(_) => Some(value.clone()),
+            _ => None
+        });
+        scalar.set_parse_literal(|value| match *value {
+            ast::Value::BooleanValue { value, .. } => Some(Value::Boolean(value)),
+            _ => None
+        });
+        scalar
+    }
+
+    pub fn id() -> Scalar {
+        let mut scalar = Scalar::new("ID", |value| match *value {
+            Value::String(_) => Some(value.clone()),
+            Value::Int(value) => Some(Value::String(value.to_string())),
+            _ => None
+        });
+        scalar.set_parse_value(|value| match *value {
+            Value::String(_) => Some(value.clone()),
+            Value::Int(value) => Some(Value::String(value.to_string())),
+            _ => None
+        });
+        scalar.set_parse_literal(|value| match *value {
+            ast::Value::StringValue { ref value, .. } | ast::Value::IntValue { ref value, .. } => Some(Value::String(value.clone())),
+            _ => None
+        });
+        scalar
+    }
+
+    pub fn serialize(&self, value: &Value) -> Option<Value> {
+        (self.serialize)(value)
+    }
+
+    /// Turns an input value into the scalar's internal value, or `None` if
+    /// the value isn't valid for the scalar.
+    pub fn parse_value(&self, value: &Value) -> Option<Value> {
+        (self.parse_value)(value)
+    }
+
+    /// Like `parse_value`, for a literal written in a query.
+    pub fn parse_literal(&self, value: &ast::Value) -> Option<Value> {
+        match self.parse_literal {
+            Some(parse_literal) => parse_literal(value),
+            None => self.parse_value(&literal(value)?)
+        }
+    }
+}
+
+/// The runtime value of a scalar literal, before it's checked against a
+/// type.
+fn literal(value: &ast::Value) -> Option<Value> {
+    match *value {
+        ast::Value::IntValue { ref value, .. } => value.parse().ok().map(Value::Int),
+        ast::Value::FloatValue { ref value, .. } => value.parse().ok().map(Value::Float),
+        ast::Value::StringValue { ref value, .. } => Some(Value::String(value.clone())),
+        ast::Value::BooleanValue { value, .. } => Some(Value::Boolean(value)),
+        ast::Value::EnumValue { ref value, .. } => Some(Value::Enum(value.clone())),
+        _ => None
+    }
 }
 
 pub struct Enum {
@@ -10,11 +382,223 @@ pub struct Enum {
     pub values: HashMap<String, EnumValue>
 }
 
+impl Enum {
+    /// Accepts either the name of one of the values or its internal value.
+    pub fn serialize(&self, value: &Value) -> Option<Value> {
+        match *value {
+            Value::Enum(ref name) |
+            Value::String(ref name) if self.values.contains_key(name) => Some(Value::Enum(name.clone())),
+            Value::Int(value) => {
+                self.values.iter()
+                    .find(|&(_, enum_value)| enum_value.value as i64 == value)
+                    .map(|(name, _)| Value::Enum(name.clone()))
+            },
+            _ => None
+        }
+    }
+
+    /// Accepts the name of one of the values. Names arrive as strings from
+    /// JSON variables and as enums from literals.
+    pub fn parse_value(&self, value: &Value) -> Option<Value> {
+        match *value {
+            Value::Enum(ref name) |
+            Value::String(ref name) if self.values.contains_key(name) => Some(Value::Enum(name.clone())),
+            _ => None
+        }
+    }
+}
+
 pub struct EnumValue {
     pub value: usize,
     pub description: String
 }
 
 pub struct Object {
-    pub name: String
+    pub name: String,
+    fields: Vec<Field>,
+    /// Names of the interfaces the object implements.
+    interfaces: Vec<String>,
+    is_type_of: Option<IsTypeOf>
+}
+
+impl Object {
+    pub fn new(name: &str) -> Object {
+        Object {
+            name: name.to_owned(),
+            fields: vec![],
+            interfaces: vec![],
+            is_type_of: None
+        }
+    }
+
+    pub fn add_field(&mut self, field: Field) {
+        self.fields.push(field);
+    }
+
+    pub fn add_interface(&mut self, name: &str) {
+        self.interfaces.push(name.to_owned());
+    }
+
+    /// Lets interfaces and unions without a `resolve_type` find out whether
+    /// a value is of this type. Values completed as this type are checked
+    /// too, and a value it rejects is a field error.
+    pub fn set_is_type_of<F>(&mut self, is_type_of: F) where F: Fn(&Value, &Context) -> bool + Send + Sync + 'static {
+        self.is_type_of = Some(Box::new(is_type_of));
+    }
+
+    /// `None` if the object has no `is_type_of` check.
+    pub fn is_type_of(&self, value: &Value, context: &Context) -> Option<bool> {
+        self.is_type_of.as_ref().map(|is_type_of| is_type_of(value, context))
+    }
+}
+
+pub struct Interface {
+    pub name: String,
+    fields: Vec<Field>,
+    resolve_type: Option<TypeResolver>
+}
+
+impl Interface {
+    pub fn new(name: &str) -> Interface {
+        Interface {
+            name: name.to_owned(),
+            fields: vec![],
+            resolve_type: None
+        }
+    }
+
+    pub fn add_field(&mut self, field: Field) {
+        self.fields.push(field);
+    }
+
+    /// Sets how to name the object type of a value. Without it, each
+    /// implementation's `is_type_of` is asked in turn.
+    pub fn set_resolve_type<F>(&mut self, resolve_type: F) where F: Fn(&Value, &Context) -> Option<String> + Send + Sync + 'static {
+        self.resolve_type = Some(Box::new(resolve_type));
+    }
+}
+
+pub struct Union {
+    pub name: String,
+    /// Names of the member object types.
+    types: Vec<String>,
+    resolve_type: Option<TypeResolver>
+}
+
+impl Union {
+    pub fn new(name: &str) -> Union {
+        Union {
+            name: name.to_owned(),
+            types: vec![],
+            resolve_type: None
+        }
+    }
+
+    pub fn add_type(&mut self, name: &str) {
+        self.types.push(name.to_owned());
+    }
+
+    /// Sets how to name the object type of a value. Without it, each
+    /// member's `is_type_of` is asked in turn.
+    pub fn set_resolve_type<F>(&mut self, resolve_type: F) where F: Fn(&Value, &Context) -> Option<String> + Send + Sync + 'static {
+        self.resolve_type = Some(Box::new(resolve_type));
+    }
+}
+
+pub struct InputObject {
+    pub name: String,
+    fields: Vec<InputValue>
+}
+
+impl InputObject {
+    pub fn new(name: &str) -> InputObject {
+        InputObject {
+            name: name.to_owned(),
+            fields: vec![]
+        }
+    }
+
+    pub fn add_field(&mut self, field: InputValue) {
+        self.fields.push(field);
+    }
+
+    pub fn fields(&self) -> &[InputValue] {
+        &self.fields
+    }
+}
+
+/// A field of an input object or an argument of a field.
+pub struct InputValue {
+    pub name: String,
+    pub value_type: TypeRef,
+    /// Used when the field is left out of the input.
+    pub default_value: Option<Value>
+}
+
+impl InputValue {
+    pub fn new(name: &str, value_type: TypeRef) -> InputValue {
+        InputValue {
+            name: name.to_owned(),
+            value_type,
+            default_value: None
+        }
+    }
+
+    pub fn set_default_value(&mut self, default_value: Value) {
+        self.default_value = Some(default_value);
+    }
+}
+
+pub struct Field {
+    pub name: String,
+    pub field_type: TypeRef,
+    arguments: Vec<InputValue>,
+    resolver: Option<FieldResolver>
+}
+
+enum FieldResolver {
+    Sync(Resolver),
+    Async(AsyncResolver)
+}
+
+impl Field {
+    pub fn new(name: &str, field_type: TypeRef) -> Field {
+        Field {
+            name: name.to_owned(),
+            field_type,
+            arguments: vec![],
+            resolver: None
+        }
+    }
+
+    pub fn add_argument(&mut self, argument: InputValue) {
+        self.arguments.push(argument);
+    }
+
+    pub fn arguments(&self) -> &[InputValue] {
+        &self.arguments
+    }
+
+    pub fn set_resolver<F>(&mut self, resolver: F) where F: Fn(&ResolveInfo) -> FieldResult + Send + Sync + 'static {
+        self.resolver = Some(FieldResolver::Sync(Box::new(resolver)));
+    }
+
+    /// Sets a resolver that returns a future, e.g.
+    /// `field.set_async_resolver(|info| Box::pin(async move { ... }))`.
+    /// Sibling fields wait on their futures at the same time. If a non-null
+    /// sibling fails, the future still runs to completion and its errors are
+    /// reported, but its value is discarded.
+    pub fn set_async_resolver<F>(&mut self, resolver: F) where F: for<'a> Fn(&'a ResolveInfo<'a>) -> FieldFuture<'a> + Send + Sync + 'static {
+        self.resolver = Some(FieldResolver::Async(Box::new(resolver)));
+    }
+
+    /// Runs the resolver. Fields without one resolve to the parent object's
+    /// value of the same name.
+    pub fn resolve<'a>(&'a self, info: &'a ResolveInfo<'a>) -> FieldFuture<'a> {
+        match self.resolver {
+            Some(FieldResolver::Sync(ref resolver)) => Box::pin(future::ready(resolver(info))),
+            Some(FieldResolver::Async(ref resolver)) => resolver(info),
+            None => Box::pin(future::ready(Ok(info.parent.get(&self.name).cloned().unwrap_or(Value::Null))))
+        }
+    }
 }
@@ -0,0 +1,600 @@
+//! Reading and writing values as JSON, so responses can go over HTTP without
+//! pulling in a JSON library. With the `serde` feature, values and responses
+//! also implement serde's traits and convert to and from `serde_json`.
+
+use crate::errors::{GraphQLError, PathSegment};
+use crate::request::Response;
+use crate::value::{Value, Map};
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Deepest nesting `from_str` accepts, to keep hostile input from
+/// overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+#[derive(PartialEq, Debug)]
+pub struct JsonError {
+    pub description: String,
+    /// Byte offset into the input where the error was found.
+    pub position: usize
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.description, self.position)
+    }
+}
+
+impl Error for JsonError {}
+
+pub fn to_string(value: &Value) -> String {
+    let mut out = vec![];
+    to_writer(&mut out, value).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Writes `value` as compact JSON. Nothing is buffered, so wrap unbuffered
+/// writers such as sockets in a `BufWriter`.
+pub fn to_writer<W: Write>(writer: &mut W, value: &Value) -> io::Result<()> {
+    match *value {
+        Value::Null => writer.write_all(b"null"),
+        Value::Int(value) => write!(writer, "{}", value),
+        // Debug formatting is the shortest that reads back the same number and
+        // switches to exponents for very large or small ones.
+        Value::Float(value) if value.is_finite() => write!(writer, "{:?}", value),
+        Value::Float(_) => writer.write_all(b"null"),
+        Value::String(ref value) |
+        Value::Enum(ref value) => write_string(writer, value),
+        Value::Boolean(value) => writer.write_all(if value { b"true" } else { b"false" }),
+        Value::List(ref values) => {
+            writer.write_all(b"[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                to_writer(writer, value)?;
+            }
+            writer.write_all(b"]")
+        },
+        Value::Object(ref fields) => write_object(writer, fields.iter().map(|(key, value)| (&key[..], value)))
+    }
+}
+
+/// Writes a response as JSON without first copying it into a `Value`.
+pub fn write_response<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+    writer.write_all(b"{")?;
+    let mut first = true;
+    if !response.errors.is_empty() {
+        writer.write_all(b"\"errors\":[")?;
+        for (i, error) in response.errors.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            write_error(writer, error)?;
+        }
+        writer.write_all(b"]")?;
+        first = false;
+    }
+    for (key, value) in [("data", &response.data), ("extensions", &response.extensions)] {
+        if let Some(ref value) = *value {
+            if !first {
+                writer.write_all(b",")?;
+            }
+            write_string(writer, key)?;
+            writer.write_all(b":")?;
+            to_writer(writer, value)?;
+            first = false;
+        }
+    }
+    writer.write_all(b"}")
+}
+
+/// Writes an error in the shape `GraphQLError::to_value` gives it.
+fn write_error<W: Write>(writer: &mut W, error: &GraphQLError) -> io::Result<()> {
+    writer.write_all(b"{\"message\":")?;
+    write_string(writer, &error.message)?;
+    if !error.locations.is_empty() {
+        writer.write_all(b",\"locations\":[")?;
+        for (i, location) in error.locations.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            write!(writer, "{{\"line\":{},\"column\":{}}}", location.line, location.column)?;
+        }
+        writer.write_all(b"]")?;
+    }
+    if !error.path.is_empty() {
+        writer.write_all(b",\"path\":[")?;
+        for (i, segment) in error.path.iter().enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            match *segment {
+                PathSegment::Key(ref key) => write_string(writer, key)?,
+                PathSegment::Index(index) => write!(writer, "{}", index)?
+            }
+        }
+        writer.write_all(b"]")?;
+    }
+    writer.write_all(b"}")
+}
+
+fn write_object<'a, W, I>(writer: &mut W, fields: I) -> io::Result<()>
+    where W: Write, I: Iterator<Item = (&'a str, &'a Value)> {
+    writer.write_all(b"{")?;
+    for (i, (key, value)) in fields.enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        write_string(writer, key)?;
+        writer.write_all(b":")?;
+        to_writer(writer, value)?;
+    }
+    writer.write_all(b"}")
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    let bytes = value.as_bytes();
+    let mut start = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let escaped: &[u8] = match byte {
+            b'"'  => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08  => b"\\b",
+            0x0c  => b"\\f",
+            0x00..=0x1f => {
+                writer.write_all(&bytes[start..i])?;
+                write!(writer, "\\u{:04x}", byte)?;
+                start = i + 1;
+                continue;
+            },
+            _ => continue
+        };
+        writer.write_all(&bytes[start..i])?;
+        writer.write_all(escaped)?;
+        start = i + 1;
+    }
+    writer.write_all(&bytes[start..])?;
+    writer.write_all(b"\"")
+}
+
+/// Reads a JSON document. Numbers without a fraction or exponent that fit in
+/// an `i64` become `Int`s; all others become `Float`s. Repeated keys keep
+/// the last value.
+pub fn from_str(input: &str) -> Result<Value, JsonError> {
+    let mut reader = Reader { input: input.as_bytes(), position: 0, depth: 0 };
+    reader.skip_whitespace();
+    let value = reader.read_value()?;
+    reader.skip_whitespace();
+    if reader.position < reader.input.len() {
+        return Err(reader.error("Unexpected trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Reads a JSON object, giving its fields in order along with the position
+/// each value starts at, so callers that check the fields can point at the
+/// offending one.
+pub fn fields_from_str(input: &str) -> Result<Vec<(String, usize, Value)>, JsonError> {
+    let mut reader = Reader { input: input.as_bytes(), position: 0, depth: 1 };
+    reader.skip_whitespace();
+    if reader.peek() != Some(b'{') {
+        return Err(reader.error("Expected a JSON object"));
+    }
+    let mut fields = vec![];
+    reader.read_fields(|key, position, value| fields.push((key, position, value)))?;
+    reader.skip_whitespace();
+    if reader.position < reader.input.len() {
+        return Err(reader.error("Unexpected trailing characters"));
+    }
+    Ok(fields)
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    position: usize,
+    depth: usize
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, description: &str) -> JsonError {
+        JsonError {
+            description: description.to_owned(),
+            position: self.position
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &[u8]) -> Result<(), JsonError> {
+        if self.input[self.position..].starts_with(literal) {
+            self.position += literal.len();
+            Ok(())
+        } else {
+            Err(self.error("Unexpected character"))
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Value, JsonError> {
+        match self.peek() {
+            Some(b'n') => self.expect(b"null").map(|_| Value::Null),
+            Some(b't') => self.expect(b"true").map(|_| Value::Boolean(true)),
+            Some(b'f') => self.expect(b"false").map(|_| Value::Boolean(false)),
+            Some(b'"') => self.read_string().map(Value::String),
+            Some(b'[') => self.nested(Reader::read_list),
+            Some(b'{') => self.nested(Reader::read_object),
+            Some(b'-') | Some(b'0'..=b'9') => self.read_number(),
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of input"))
+        }
+    }
+
+    fn nested<F>(&mut self, read: F) -> Result<Value, JsonError> where F: Fn(&mut Reader<'a>) -> Result<Value, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("Nested too deeply"));
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn read_list(&mut self) -> Result<Value, JsonError> {
+        self.position += 1;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Value::List(values));
+        }
+        loop {
+            self.skip_whitespace();
+            values.push(self.read_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Value::List(values));
+                },
+                _ => return Err(self.error("Expected , or ]"))
+            }
+        }
+    }
+
+    fn read_object(&mut self) -> Result<Value, JsonError> {
+        let mut fields = Map::new();
+        self.read_fields(|key, _, value| {
+            fields.insert(key, value);
+        })?;
+        Ok(Value::Object(fields))
+    }
+
+    /// Reads the fields of the object at the current position, handing each
+    /// to `field` with the position its value starts at.
+    fn read_fields<F: FnMut(String, usize, Value)>(&mut self, mut field: F) -> Result<(), JsonError> {
+        self.position += 1;
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expected a string key"));
+            }
+            let key = self.read_string()?;
+            self.skip_whitespace();
+            self.expect(b":")?;
+            self.skip_whitespace();
+            let position = self.position;
+            let value = self.read_value()?;
+            field(key, position, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(());
+                },
+                _ => return Err(self.error("Expected , or }"))
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Value, JsonError> {
+        let start = self.position;
+        let mut is_float = false;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.position += 1,
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("Expected a digit"))
+        }
+        if self.peek() == Some(b'.') {
+            is_float = true;
+            self.position += 1;
+            self.expect_digits()?;
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            is_float = true;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            self.expect_digits()?;
+        }
+
+        // Only ASCII digits and signs were consumed.
+        let text = ::std::str::from_utf8(&self.input[start..self.position]).unwrap();
+        if !is_float {
+            if let Ok(value) = text.parse() {
+                return Ok(Value::Int(value));
+            }
+        }
+        text.parse().map(Value::Float).map_err(|_| JsonError {
+            description: "Invalid number".to_owned(),
+            position: start
+        })
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect_digits(&mut self) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(b'0'..=b'9') => {
+                self.skip_digits();
+                Ok(())
+            },
+            _ => Err(self.error("Expected a digit"))
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, JsonError> {
+        self.position += 1;
+        let mut out = vec![];
+        loop {
+            let start = self.position;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.position += 1;
+            }
+            out.extend_from_slice(&self.input[start..self.position]);
+
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    // The input was a str and escapes are written as UTF-8.
+                    return Ok(String::from_utf8(out).unwrap());
+                },
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"')  => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/')  => '/',
+                        Some(b'b')  => '\u{8}',
+                        Some(b'f')  => '\u{c}',
+                        Some(b'n')  => '\n',
+                        Some(b'r')  => '\r',
+                        Some(b't')  => '\t',
+                        Some(b'u')  => {
+                            self.position += 1;
+                            let c = self.read_unicode_escape()?;
+                            let mut buffer = [0; 4];
+                            out.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                            continue;
+                        },
+                        _ => return Err(self.error("Invalid escape"))
+                    };
+                    self.position += 1;
+                    let mut buffer = [0; 4];
+                    out.extend_from_slice(escaped.encode_utf8(&mut buffer).as_bytes());
+                },
+                Some(_) => return Err(self.error("Unescaped control character in string")),
+                None => return Err(self.error("Unterminated string"))
+            }
+        }
+    }
+
+    /// Reads the digits of a `\u` escape, and a second escape if the first
+    /// is a high surrogate.
+    fn read_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.read_hex()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return ::std::char::from_u32(high).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+        if !self.input[self.position..].starts_with(b"\\u") {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        self.position += 2;
+        let low = self.read_hex()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        ::std::char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn read_hex(&mut self) -> Result<u32, JsonError> {
+        let digits = self.input.get(self.position..self.position + 4)
+            .and_then(|digits| ::std::str::from_utf8(digits).ok())
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        self.position += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use crate::request::Response;
+    use crate::value::{Value, Map};
+
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::de::{Visitor, SeqAccess, MapAccess};
+    use serde::ser::{SerializeMap, SerializeSeq};
+    use serde_json::{self, Value as Json};
+
+    use std::fmt;
+
+    impl Serialize for Value {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match *self {
+                Value::Null => serializer.serialize_unit(),
+                Value::Int(value) => serializer.serialize_i64(value),
+                Value::Float(value) if value.is_finite() => serializer.serialize_f64(value),
+                Value::Float(_) => serializer.serialize_unit(),
+                Value::String(ref value) |
+                Value::Enum(ref value) => serializer.serialize_str(value),
+                Value::Boolean(value) => serializer.serialize_bool(value),
+                Value::List(ref values) => {
+                    let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                    for value in values {
+                        seq.serialize_element(value)?;
+                    }
+                    seq.end()
+                },
+                Value::Object(ref fields) => fields.serialize(serializer)
+            }
+        }
+    }
+
+    impl Serialize for Map {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(self.len()))?;
+            for (key, value) in self {
+                map.serialize_entry(key, value)?;
+            }
+            map.end()
+        }
+    }
+
+    impl Serialize for Response {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.to_value().serialize(serializer)
+        }
+    }
+
+    /// Object fields keep the order they're read in.
+    impl<'de> Deserialize<'de> for Value {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+            deserializer.deserialize_any(ValueVisitor)
+        }
+    }
+
+    struct ValueVisitor;
+
+    impl<'de> Visitor<'de> for ValueVisitor {
+        type Value = Value;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a JSON value")
+        }
+
+        fn visit_unit<E>(self) -> Result<Value, E> {
+            Ok(Value::Null)
+        }
+
+        fn visit_none<E>(self) -> Result<Value, E> {
+            Ok(Value::Null)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+            Value::deserialize(deserializer)
+        }
+
+        fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+            Ok(Value::Boolean(value))
+        }
+
+        fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+            Ok(Value::Int(value))
+        }
+
+        fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+            Ok(if value <= i64::MAX as u64 { Value::Int(value as i64) } else { Value::Float(value as f64) })
+        }
+
+        fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+            Ok(Value::Float(value))
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+            Ok(Value::String(value.to_owned()))
+        }
+
+        fn visit_string<E>(self, value: String) -> Result<Value, E> {
+            Ok(Value::String(value))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+            let mut values = vec![];
+            while let Some(value) = seq.next_element()? {
+                values.push(value);
+            }
+            Ok(Value::List(values))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+            let mut fields = Map::new();
+            while let Some((key, value)) = map.next_entry()? {
+                fields.insert(key, value);
+            }
+            Ok(Value::Object(fields))
+        }
+    }
+
+    impl From<Json> for Value {
+        fn from(json: Json) -> Value {
+            match json {
+                Json::Null => Value::Null,
+                Json::Bool(value) => Value::Boolean(value),
+                Json::Number(number) => match number.as_i64() {
+                    Some(value) => Value::Int(value),
+                    None => number.as_f64().map_or(Value::Null, Value::Float)
+                },
+                Json::String(value) => Value::String(value),
+                Json::Array(values) => Value::List(values.into_iter().map(Value::from).collect()),
+                Json::Object(fields) => Value::Object(fields.into_iter().map(|(key, value)| (key, Value::from(value))).collect())
+            }
+        }
+    }
+
+    /// Enums become strings, and floats that aren't finite become null.
+    impl From<Value> for Json {
+        fn from(value: Value) -> Json {
+            match value {
+                Value::Null => Json::Null,
+                Value::Int(value) => Json::from(value),
+                Value::Float(value) => serde_json::Number::from_f64(value).map_or(Json::Null, Json::Number),
+                Value::String(value) | Value::Enum(value) => Json::String(value),
+                Value::Boolean(value) => Json::Bool(value),
+                Value::List(values) => Json::Array(values.into_iter().map(Json::from).collect()),
+                Value::Object(fields) => Json::Object(fields.into_iter().map(|(key, value)| (key, Json::from(value))).collect())
+            }
+        }
+    }
+}
@@ -0,0 +1,352 @@
+pub mod json;
+
+use self::json::JsonError;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
+
+/// A runtime value: a variable's value, something a resolver returned, or a
+/// part of a response.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Enum(String),
+    List(Vec<Value>),
+    Object(Map)
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        *self == Value::Null
+    }
+
+    /// Looks up a field of an object value.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match *self {
+            Value::Object(ref fields) => fields.get(key),
+            _ => None
+        }
+    }
+
+    /// The value as compact JSON. Floats that aren't finite become null.
+    pub fn to_json(&self) -> String {
+        json::to_string(self)
+    }
+
+    pub fn from_json(input: &str) -> Result<Value, JsonError> {
+        json::from_str(input)
+    }
+}
+
+/// Maps with more fields than this also index their keys. Below it, a
+/// linear scan is faster than hashing.
+const INDEX_THRESHOLD: usize = 8;
+
+/// The fields of an object value, kept in insertion order, which for
+/// results is the order of the query's selections. Large maps, such as
+/// objects read from JSON, keep an index so that lookups and inserts don't
+/// depend on the number of fields.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Value)>,
+    index: Option<HashMap<String, usize>>
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map { entries: vec![], index: None }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Map {
+        Map { entries: Vec::with_capacity(capacity), index: None }
+    }
+
+    /// Sets `key` to `value`. A key that's already present keeps its
+    /// position and its old value is returned.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        if let Some(position) = self.position(&key) {
+            return Some(::std::mem::replace(&mut self.entries[position].1, value));
+        }
+        match self.index {
+            Some(ref mut index) => { index.insert(key.clone(), self.entries.len()); },
+            None if self.entries.len() >= INDEX_THRESHOLD => {
+                let mut index: HashMap<String, usize> = self.entries.iter().enumerate()
+                    .map(|(position, (key, _))| (key.clone(), position))
+                    .collect();
+                index.insert(key.clone(), self.entries.len());
+                self.index = Some(index);
+            },
+            None => {}
+        }
+        self.entries.push((key, value));
+        None
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.position(key).map(|position| &self.entries[position].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.position(key).map(move |position| &mut self.entries[position].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Removes `key`, keeping the order of the remaining fields.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let position = self.position(key)?;
+        let (_, value) = self.entries.remove(position);
+        if let Some(ref mut index) = self.index {
+            index.remove(key);
+            for later in index.values_mut().filter(|later| **later > position) {
+                *later -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        match self.index {
+            Some(ref index) => index.get(key).cloned(),
+            None => self.entries.iter().position(|(k, _)| k == key)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, (String, Value)> {
+        self.entries.iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(key, _)| &key[..])
+    }
+}
+
+/// Maps are equal when they have the same fields in the same order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.entries.iter().map(|(key, value)| (key, value))).finish()
+    }
+}
+
+impl FromIterator<(String, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Map {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(String, Value)> for Map {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, Value);
+    type IntoIter = vec::IntoIter<(String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = &'a (String, Value);
+    type IntoIter = slice::Iter<'a, (String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl From<Vec<(String, Value)>> for Map {
+    fn from(entries: Vec<(String, Value)>) -> Map {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<Map> for Value {
+    fn from(map: Map) -> Value {
+        Value::Object(map)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Value {
+        Value::Int(i64::from(value))
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Value {
+        Value::Int(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Value {
+        Value::Int(i64::from(value))
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Value {
+        Value::Float(f64::from(value))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Value {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Boolean(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Value {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::String(value)
+    }
+}
+
+/// `None` becomes null.
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Value {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Value {
+        Value::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Keys are sorted, since a `HashMap` has no order of its own.
+impl<K: Into<String>, T: Into<Value>> From<HashMap<K, T>> for Value {
+    fn from(values: HashMap<K, T>) -> Value {
+        let mut entries: Vec<(String, Value)> = values.into_iter().map(|(key, value)| (key.into(), value.into())).collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Value::Object(entries.into())
+    }
+}
+
+impl<K: Into<String>, T: Into<Value>> From<BTreeMap<K, T>> for Value {
+    fn from(values: BTreeMap<K, T>) -> Value {
+        Value::Object(values.into_iter().map(|(key, value)| (key.into(), value.into())).collect())
+    }
+}
+
+/// Conversion out of a `Value`, used to read typed arguments.
+pub trait FromValue: Sized {
+    /// `None` if the value doesn't hold a `Self`.
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: &Value) -> Option<Value> {
+        Some(value.clone())
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Option<i64> {
+        match *value {
+            Value::Int(value) => Some(value),
+            _ => None
+        }
+    }
+}
+
+impl FromValue for i32 {
+    fn from_value(value: &Value) -> Option<i32> {
+        match *value {
+            Value::Int(value) if value >= i64::from(i32::MIN) && value <= i64::from(i32::MAX) => Some(value as i32),
+            _ => None
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Option<f64> {
+        match *value {
+            Value::Float(value) => Some(value),
+            Value::Int(value) => Some(value as f64),
+            _ => None
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Option<bool> {
+        match *value {
+            Value::Boolean(value) => Some(value),
+            _ => None
+        }
+    }
+}
+
+/// Strings and enum names.
+impl FromValue for String {
+    fn from_value(value: &Value) -> Option<String> {
+        match *value {
+            Value::String(ref value) | Value::Enum(ref value) => Some(value.clone()),
+            _ => None
+        }
+    }
+}
+
+/// Null becomes `Some(None)`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Option<Option<T>> {
+        match *value {
+            Value::Null => Some(None),
+            _ => T::from_value(value).map(Some)
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Option<Vec<T>> {
+        match *value {
+            Value::List(ref values) => values.iter().map(T::from_value).collect(),
+            _ => None
+        }
+    }
+}
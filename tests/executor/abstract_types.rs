@@ -0,0 +1,161 @@
+use graphql::types::definition::*;
+use graphql::request::{Context, Request};
+use graphql::value::Value;
+use graphql::GraphQL;
+
+fn pets() -> Value {
+    Value::from_json(r#"[
+        {"kind": "dog", "name": "Odie", "woofs": true},
+        {"kind": "cat", "name": "Garfield", "meows": false}
+    ]"#).unwrap()
+}
+
+fn is_kind(kind: &'static str) -> impl Fn(&Value, &Context) -> bool {
+    move |value, _| value.get("kind") == Some(&Value::String(kind.to_owned()))
+}
+
+/// A schema with a `Pet` interface and a `CatOrDog` union over the same
+/// types, and a `dogs` field that returns cats as well. `resolve_type`, if given, is used for both.
+fn schema(with_is_type_of: bool, resolve_type: Option<fn(&Value, &Context) -> Option<String>>) -> Schema {
+    let mut pet = Interface::new("Pet");
+    pet.add_field(Field::new("name", TypeRef::named("String")));
+
+    let mut dog = Object::new("Dog");
+    dog.add_interface("Pet");
+    dog.add_field(Field::new("name", TypeRef::named("String")));
+    dog.add_field(Field::new("woofs", TypeRef::named("Boolean")));
+
+    let mut cat = Object::new("Cat");
+    cat.add_interface("Pet");
+    cat.add_field(Field::new("name", TypeRef::named("String")));
+    cat.add_field(Field::new("meows", TypeRef::named("Boolean")));
+
+    let mut cat_or_dog = Union::new("CatOrDog");
+    cat_or_dog.add_type("Dog");
+    cat_or_dog.add_type("Cat");
+
+    if with_is_type_of {
+        dog.set_is_type_of(is_kind("dog"));
+        cat.set_is_type_of(is_kind("cat"));
+    }
+    if let Some(resolve_type) = resolve_type {
+        pet.set_resolve_type(resolve_type);
+        cat_or_dog.set_resolve_type(resolve_type);
+    }
+
+    let mut query = Object::new("Query");
+    let mut pets_field = Field::new("pets", TypeRef::list(TypeRef::named("Pet")));
+    pets_field.set_resolver(|_| Ok(pets()));
+    query.add_field(pets_field);
+    let mut union_field = Field::new("catOrDogs", TypeRef::list(TypeRef::named("CatOrDog")));
+    union_field.set_resolver(|_| Ok(pets()));
+    query.add_field(union_field);
+    let mut dogs_field = Field::new("dogs", TypeRef::list(TypeRef::named("Dog")));
+    dogs_field.set_resolver(|_| Ok(pets()));
+    query.add_field(dogs_field);
+
+    let mut schema = Schema::new(query);
+    schema.add_type(pet);
+    schema.add_type(dog);
+    schema.add_type(cat);
+    schema.add_type(cat_or_dog);
+    schema
+}
+
+fn run(schema: &Schema, query: &str) -> String {
+    GraphQL::execute(schema, &Request::new(query)).to_json()
+}
+
+const PETS_QUERY: &str = "{ pets { name ... on Dog { woofs } ... on Cat { meows } } }";
+const UNION_QUERY: &str = "{ catOrDogs { __typename ... on Dog { name woofs } ... on Cat { name meows } } }";
+
+#[test]
+fn it_uses_is_type_of_for_interfaces() {
+    assert_eq!(
+        r#"{"data":{"pets":[{"name":"Odie","woofs":true},{"name":"Garfield","meows":false}]}}"#,
+        run(&schema(true, None), PETS_QUERY)
+    );
+}
+
+#[test]
+fn it_uses_is_type_of_for_unions() {
+    assert_eq!(
+        r#"{"data":{"catOrDogs":[{"__typename":"Dog","name":"Odie","woofs":true},{"__typename":"Cat","name":"Garfield","meows":false}]}}"#,
+        run(&schema(true, None), UNION_QUERY)
+    );
+}
+
+#[test]
+fn it_uses_resolve_type_for_interfaces_and_unions() {
+    let schema = schema(false, Some(|value, _| match value.get("kind") {
+        Some(Value::String(kind)) if kind == "dog" => Some("Dog".to_owned()),
+        Some(Value::String(kind)) if kind == "cat" => Some("Cat".to_owned()),
+        _ => None
+    }));
+    assert_eq!(
+        r#"{"data":{"pets":[{"name":"Odie","woofs":true},{"name":"Garfield","meows":false}]}}"#,
+        run(&schema, PETS_QUERY)
+    );
+    assert_eq!(
+        r#"{"data":{"catOrDogs":[{"__typename":"Dog","name":"Odie","woofs":true},{"__typename":"Cat","name":"Garfield","meows":false}]}}"#,
+        run(&schema, UNION_QUERY)
+    );
+}
+
+#[test]
+fn it_reports_values_that_resolve_to_no_type() {
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"Abstract type \"Pet\" must resolve to an Object type at runtime for field \"Query.pets\". "#,
+                r#"Either the \"Pet\" type should provide a \"resolve_type\" function or each possible type should provide an \"is_type_of\" function.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["pets",0]},"#,
+                r#"{"message":"Abstract type \"Pet\" must resolve to an Object type at runtime for field \"Query.pets\". "#,
+                r#"Either the \"Pet\" type should provide a \"resolve_type\" function or each possible type should provide an \"is_type_of\" function.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["pets",1]}],"#,
+                r#""data":{"pets":[null,null]}}"#),
+        run(&schema(false, None), PETS_QUERY)
+    );
+}
+
+#[test]
+fn it_reports_values_that_resolve_to_an_impossible_type() {
+    let schema = schema(false, Some(|value, _| match value.get("kind") {
+        Some(Value::String(kind)) if kind == "dog" => Some("Query".to_owned()),
+        _ => Some("Lion".to_owned())
+    }));
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"Runtime Object type \"Query\" is not a possible type for \"CatOrDog\".","#,
+                r#""locations":[{"line":1,"column":3}],"path":["catOrDogs",0]},"#,
+                r#"{"message":"Abstract type \"CatOrDog\" was resolved to a type \"Lion\" that does not exist inside the schema.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["catOrDogs",1]}],"#,
+                r#""data":{"catOrDogs":[null,null]}}"#),
+        run(&schema, UNION_QUERY)
+    );
+}
+
+#[test]
+fn it_checks_is_type_of_for_object_types() {
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Expected value of type \"Dog\" but got: {\"kind\":\"cat\",\"name\":\"Garfield\",\"meows\":false}.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["dogs",1]}],"data":{"dogs":[{"name":"Odie"},null]}}"#),
+        run(&schema(true, None), "{ dogs { name } }")
+    );
+    assert_eq!(r#"{"data":{"dogs":[{"name":"Odie"},{"name":"Garfield"}]}}"#, run(&schema(false, None), "{ dogs { name } }"));
+
+    let schema = schema(true, Some(|_, _| Some("Dog".to_owned())));
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Expected value of type \"Dog\" but got: {\"kind\":\"cat\",\"name\":\"Garfield\",\"meows\":false}.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["pets",1]}],"data":{"pets":[{"name":"Odie"},null]}}"#),
+        run(&schema, "{ pets { name } }")
+    );
+}
+
+#[test]
+fn it_lists_possible_types() {
+    let schema = schema(false, None);
+    let names = |name: &str| schema.possible_types(schema.get_type(name).unwrap()).iter().map(|object| object.name.clone()).collect::<Vec<_>>();
+    assert_eq!(vec!["Cat", "Dog"], names("Pet"));
+    assert_eq!(vec!["Dog", "Cat"], names("CatOrDog"));
+    assert_eq!(vec!["Query"], names("Query"));
+}
@@ -0,0 +1,125 @@
+use graphql::types::definition::*;
+use graphql::request::Request;
+use graphql::value::Value;
+use graphql::GraphQL;
+
+fn schema() -> Schema {
+    let mut complex = InputObject::new("ComplexInput");
+    complex.add_field(InputValue::new("required", TypeRef::non_null(TypeRef::named("Boolean"))));
+    let mut optional = InputValue::new("optional", TypeRef::named("Int"));
+    optional.set_default_value(Value::Int(7));
+    complex.add_field(optional);
+
+    let mut query = Object::new("Query");
+
+    // Echoes its argument, telling a missing argument apart from null.
+    let mut echo = Field::new("echo", TypeRef::named("String"));
+    let mut with_default = InputValue::new("withDefault", TypeRef::named("String"));
+    with_default.set_default_value(Value::String("default".to_owned()));
+    echo.add_argument(InputValue::new("int", TypeRef::named("Int")));
+    echo.add_argument(InputValue::new("list", TypeRef::list(TypeRef::named("Int"))));
+    echo.add_argument(InputValue::new("complex", TypeRef::named("ComplexInput")));
+    echo.add_argument(with_default);
+    echo.set_resolver(|info| {
+        let mut parts = vec![];
+        for name in &["int", "list", "complex", "withDefault"] {
+            if info.arguments.contains(name) {
+                parts.push(format!("{}: {}", name, info.arguments.value(name).unwrap().to_json()));
+            }
+        }
+        Ok(Value::String(parts.join(", ")))
+    });
+    query.add_field(echo);
+
+    let mut required = Field::new("required", TypeRef::named("Int"));
+    required.add_argument(InputValue::new("n", TypeRef::non_null(TypeRef::named("Int"))));
+    required.set_resolver(|info| Ok(Value::Int(info.arguments.get::<i64>("n")? * 2)));
+    query.add_field(required);
+
+    let mut schema = Schema::new(query);
+    schema.add_type(complex);
+    schema
+}
+
+fn run(query: &str, variables: Vec<(&str, Value)>) -> String {
+    let mut request = Request::new(query);
+    for (name, value) in variables {
+        request.variables.insert(name.to_owned(), value);
+    }
+    GraphQL::execute(&schema(), &request).to_json()
+}
+
+#[test]
+fn it_coerces_literal_arguments() {
+    assert_eq!(r#"{"data":{"echo":"withDefault: \"default\""}}"#, run("{ echo }", vec![]));
+    assert_eq!(r#"{"data":{"echo":"int: 3, withDefault: \"x\""}}"#, run(r#"{ echo(int: 3, withDefault: "x") }"#, vec![]));
+    assert_eq!(r#"{"data":{"echo":"list: [1], withDefault: \"default\""}}"#, run("{ echo(list: 1) }", vec![]));
+    assert_eq!(
+        r#"{"data":{"echo":"complex: {\"required\":true,\"optional\":7}, withDefault: \"default\""}}"#,
+        run("{ echo(complex: {required: true}) }", vec![])
+    );
+    assert_eq!(r#"{"data":{"required":6}}"#, run("{ required(n: 3) }", vec![]));
+}
+
+#[test]
+fn it_tells_explicit_null_from_missing() {
+    assert_eq!(r#"{"data":{"echo":"int: null, withDefault: null"}}"#, run("{ echo(int: null, withDefault: null) }", vec![]));
+    assert_eq!(
+        r#"{"data":{"echo":"int: null, withDefault: \"default\""}}"#,
+        run("query ($i: Int, $d: String) { echo(int: $i, withDefault: $d) }", vec![("i", Value::Null)])
+    );
+}
+
+#[test]
+fn it_takes_arguments_from_variables() {
+    assert_eq!(
+        r#"{"data":{"echo":"list: [1,2], complex: {\"required\":false,\"optional\":7}, withDefault: \"default\""}}"#,
+        run("query ($l: [Int], $b: Boolean!, $o: Int) { echo(list: $l, complex: {required: $b, optional: $o}) }",
+            vec![("l", Value::List(vec![Value::Int(1), Value::Int(2)])), ("b", Value::Boolean(false))])
+    );
+    assert_eq!(r#"{"data":{"required":8}}"#, run("query ($n: Int = 4) { required(n: $n) }", vec![]));
+}
+
+#[test]
+fn it_reports_invalid_arguments_as_field_errors() {
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"n\" of required type \"Int!\" was not provided.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["required"]}],"data":{"required":null}}"#),
+        run("{ required }", vec![])
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"n\" of non-null type \"Int!\" must not be null.","#,
+                r#""locations":[{"line":1,"column":19}],"path":["required"]}],"data":{"required":null}}"#),
+        run("query ($n: Int) { required(n: $n) }", vec![("n", Value::Null)])
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"int\" has invalid value \"three\".","#,
+                r#""locations":[{"line":1,"column":3}],"path":["echo"]}],"data":{"echo":null}}"#),
+        run(r#"{ echo(int: "three") }"#, vec![])
+    );
+}
+
+#[test]
+fn it_coerces_literals_by_their_kind() {
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"int\" has invalid value 1.0.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["echo"]}],"data":{"echo":null}}"#),
+        run("{ echo(int: 1.0) }", vec![])
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"withDefault\" has invalid value 1.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["echo"]}],"data":{"echo":null}}"#),
+        run("{ echo(withDefault: 1) }", vec![])
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"withDefault\" has invalid value RED.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["echo"]}],"data":{"echo":null}}"#),
+        run("{ echo(withDefault: RED) }", vec![])
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Argument \"n\" has invalid value 3000000000.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["required"]}],"data":{"required":null}}"#),
+        run("{ required(n: 3000000000) }", vec![])
+    );
+    assert_eq!(r#"{"data":{"required":2}}"#, run("query ($n: Int!) { required(n: $n) }", vec![("n", Value::Float(1.0))]));
+}
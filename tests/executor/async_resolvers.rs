@@ -0,0 +1,212 @@
+use graphql::executor::block_on;
+use graphql::types::definition::*;
+use graphql::request::{Context, Request};
+use graphql::value::{Value, Map};
+use graphql::errors::PathSegment;
+use graphql::GraphQL;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{self, Poll, Waker};
+
+/// Ready the second time it's polled, giving other futures a turn first.
+pub struct YieldOnce(pub bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut task::Context) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        context.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// What the resolvers did, in order.
+struct Log(Mutex<Vec<String>>);
+
+fn log(context: &Context, entry: String) {
+    context.get::<Log>().unwrap().0.lock().unwrap().push(entry);
+}
+
+/// A field that logs when it starts and finishes, and waits in between.
+fn slow_field(name: &str, field_type: TypeRef, value: Value) -> Field {
+    let mut field = Field::new(name, field_type);
+    field.set_async_resolver(move |info| {
+        let value = value.clone();
+        Box::pin(async move {
+            log(info.context, format!("start {}", info.field_name));
+            YieldOnce(false).await;
+            log(info.context, format!("end {}", info.field_name));
+            if value.is_null() {
+                return Err(format!("{} failed", info.field_name).into());
+            }
+            Ok(value)
+        })
+    });
+    field
+}
+
+fn schema() -> Schema {
+    let mut object = Object::new("Object");
+    object.add_field(slow_field("a", TypeRef::named("String"), "a".into()));
+    object.add_field(slow_field("b", TypeRef::named("String"), "b".into()));
+    object.add_field(slow_field("fails", TypeRef::named("String"), Value::Null));
+    object.add_field(slow_field("failsNonNull", TypeRef::non_null(TypeRef::named("String")), Value::Null));
+    object.add_field(slow_field("object", TypeRef::named("Object"), Value::Object(Map::new())));
+    object.add_field(slow_field("objects", TypeRef::list(TypeRef::named("Object")), vec![Map::new(), Map::new()].into()));
+    let mut sync = Field::new("sync", TypeRef::named("String"));
+    sync.set_resolver(|info| {
+        log(info.context, "sync".to_owned());
+        Ok("sync".into())
+    });
+    object.add_field(sync);
+
+    let mut mutation = Object::new("Mutation");
+    mutation.add_field(slow_field("a", TypeRef::named("String"), "a".into()));
+    mutation.add_field(slow_field("b", TypeRef::named("String"), "b".into()));
+
+    let mut schema = Schema::new(object);
+    schema.set_mutation(mutation);
+    schema
+}
+
+/// Runs `query`, returning the response and what the resolvers did.
+fn run(query: &str) -> (String, Vec<String>) {
+    let mut request = Request::new(query);
+    request.context.set(Log(Mutex::new(vec![])));
+    let response = block_on(GraphQL::execute_async(&schema(), &request)).to_json();
+    let log = request.context.get::<Log>().unwrap().0.lock().unwrap().clone();
+    (response, log)
+}
+
+fn entries(entries: &[&str]) -> Vec<String> {
+    entries.iter().map(|entry| entry.to_string()).collect()
+}
+
+#[test]
+fn it_waits_for_sibling_fields_at_the_same_time() {
+    assert_eq!(
+        (r#"{"data":{"a":"a","sync":"sync","b":"b"}}"#.to_owned(),
+         entries(&["start a", "sync", "start b", "end a", "end b"])),
+        run("{ a sync b }")
+    );
+}
+
+#[test]
+fn it_waits_for_nested_fields_and_list_items_at_the_same_time() {
+    assert_eq!(
+        (r#"{"data":{"object":{"a":"a"},"objects":[{"b":"b"},{"b":"b"}]}}"#.to_owned(),
+         entries(&["start object", "start objects", "end object", "start a", "end objects", "start b", "start b", "end a", "end b", "end b"])),
+        run("{ object { a } objects { b } }")
+    );
+}
+
+#[test]
+fn it_nulls_a_nullable_field_whose_future_fails() {
+    assert_eq!(
+        (concat!(r#"{"errors":[{"message":"fails failed","locations":[{"line":1,"column":5}],"path":["fails"]}],"#,
+                 r#""data":{"a":"a","fails":null}}"#).to_owned(),
+         entries(&["start a", "start fails", "end a", "end fails"])),
+        run("{ a fails }")
+    );
+}
+
+#[test]
+fn it_lets_the_fields_still_running_settle_when_a_non_null_sibling_fails() {
+    assert_eq!(
+        (concat!(r#"{"errors":[{"message":"failsNonNull failed","locations":[{"line":1,"column":12}],"path":["object","failsNonNull"]},"#,
+                 r#"{"message":"fails failed","locations":[{"line":1,"column":34}],"path":["object","object","fails"]}],"#,
+                 r#""data":{"object":null}}"#).to_owned(),
+         entries(&["start object", "end object", "start failsNonNull", "start object", "end failsNonNull",
+                   "end object", "start fails", "end fails"])),
+        run("{ object { failsNonNull object { fails } } }")
+    );
+}
+
+#[test]
+fn it_still_executes_mutation_fields_one_at_a_time() {
+    assert_eq!(
+        (r#"{"data":{"a":"a","b":"b"}}"#.to_owned(), entries(&["start a", "end a", "start b", "end b"])),
+        run("mutation { a b }")
+    );
+}
+
+#[test]
+fn it_waits_on_the_current_thread_when_executed_synchronously() {
+    let mut request = Request::new("{ a b }");
+    request.context.set(Log(Mutex::new(vec![])));
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, GraphQL::execute(&schema(), &request).to_json());
+}
+
+#[test]
+fn it_can_be_sent_to_another_thread() {
+    fn assert_send<T: Send>(_: &T) {}
+    let schema = schema();
+    let request = Request::new("{ sync }");
+    assert_send(&GraphQL::execute_async(&schema, &request));
+}
+
+/// Whose turn it is among `count` futures, and how often they've been
+/// polled. The last one goes first, and each wakes the one before it.
+struct Turns {
+    count: usize,
+    state: Mutex<(usize, Vec<Option<Waker>>, usize)>
+}
+
+struct Turn<'a> {
+    turns: &'a Turns,
+    index: usize
+}
+
+impl<'a> Future for Turn<'a> {
+    type Output = usize;
+
+    fn poll(self: Pin<&mut Self>, context: &mut task::Context) -> Poll<usize> {
+        let mut state = self.turns.state.lock().unwrap();
+        let (ref mut turn, ref mut wakers, ref mut polls) = *state;
+        *polls += 1;
+        if *turn != self.turns.count - 1 - self.index {
+            wakers[self.index] = Some(context.waker().clone());
+            return Poll::Pending;
+        }
+        *turn += 1;
+        if let Some(waker) = self.index.checked_sub(1).and_then(|previous| wakers[previous].take()) {
+            waker.wake();
+        }
+        Poll::Ready(self.index)
+    }
+}
+
+#[test]
+fn it_only_polls_the_list_items_that_woke() {
+    let count = 200;
+    let mut item = Object::new("Item");
+    let mut turn = Field::new("turn", TypeRef::named("Int"));
+    turn.set_async_resolver(|info| Box::pin(async move {
+        let index = match info.path[1] {
+            PathSegment::Index(index) => index,
+            _ => unreachable!()
+        };
+        let index = Turn { turns: info.context.get::<Turns>().unwrap(), index }.await;
+        Ok(Value::Int(index as i64))
+    }));
+    item.add_field(turn);
+    let mut query = Object::new("Query");
+    let mut items = Field::new("items", TypeRef::list(TypeRef::named("Item")));
+    items.set_resolver(move |_| Ok(Value::List(vec![Value::Object(Map::new()); count])));
+    query.add_field(items);
+    let mut schema = Schema::new(query);
+    schema.add_type(item);
+
+    let mut request = Request::new("{ items { turn } }");
+    request.context.set(Turns { count, state: Mutex::new((0, vec![None; count], 0)) });
+    let response = GraphQL::execute(&schema, &request);
+    assert!(response.errors.is_empty());
+    let (turn, _, polls) = *request.context.get::<Turns>().unwrap().state.lock().unwrap();
+    assert_eq!((count, 2 * count - 1), (turn, polls));
+}
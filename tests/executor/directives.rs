@@ -0,0 +1,83 @@
+use graphql::types::definition::*;
+use graphql::request::Request;
+use graphql::value::Value;
+use graphql::GraphQL;
+
+fn schema() -> Schema {
+    let mut data = Object::new("TestType");
+    data.add_field(Field::new("a", TypeRef::named("String")));
+    data.add_field(Field::new("b", TypeRef::named("String")));
+    let mut nested = Field::new("nested", TypeRef::named("TestType"));
+    nested.set_resolver(|info| Ok(info.parent.clone()));
+    data.add_field(nested);
+    Schema::new(data)
+}
+
+fn run(query: &str) -> String {
+    run_with(query, vec![])
+}
+
+fn run_with(query: &str, variables: Vec<(&str, bool)>) -> String {
+    let mut request = Request::new(query);
+    request.root_value = Value::from_json(r#"{"a": "a", "b": "b"}"#).unwrap();
+    for (name, value) in variables {
+        request.variables.insert(name.to_owned(), value.into());
+    }
+    GraphQL::execute(&schema(), &request).to_json()
+}
+
+#[test]
+fn it_works_without_directives() {
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, b }"));
+}
+
+#[test]
+fn it_honors_directives_on_fields() {
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, b @include(if: true) }"));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run("{ a, b @include(if: false) }"));
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, b @skip(if: false) }"));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run("{ a, b @skip(if: true) }"));
+}
+
+#[test]
+fn it_honors_directives_on_fragment_spreads() {
+    let query = |directive: &str| format!("{{ a, ...Frag {} }} fragment Frag on TestType {{ b }}", directive);
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run(&query("@include(if: true)")));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run(&query("@include(if: false)")));
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run(&query("@skip(if: false)")));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run(&query("@skip(if: true)")));
+}
+
+#[test]
+fn it_honors_directives_on_inline_fragments() {
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, ... on TestType @include(if: true) { b } }"));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run("{ a, ... on TestType @include(if: false) { b } }"));
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, ... @skip(if: false) { b } }"));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run("{ a, ... @skip(if: true) { b } }"));
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, ... { b } }"));
+}
+
+#[test]
+fn it_honors_both_directives_together() {
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run("{ a, b @include(if: true) @skip(if: false) }"));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run("{ a, b @include(if: true) @skip(if: true) }"));
+    assert_eq!(r#"{"data":{"a":"a"}}"#, run("{ a, b @include(if: false) @skip(if: false) }"));
+}
+
+#[test]
+fn it_takes_conditions_from_variables() {
+    let query = "query ($show: Boolean!, $hide: Boolean = true) { a, b @include(if: $show), nested @skip(if: $hide) { a } }";
+    assert_eq!(r#"{"data":{"a":"a","b":"b"}}"#, run_with(query, vec![("show", true)]));
+    assert_eq!(r#"{"data":{"a":"a","nested":{"a":"a"}}}"#, run_with(query, vec![("show", false), ("hide", false)]));
+}
+
+#[test]
+fn it_merges_fields_by_response_key() {
+    assert_eq!(
+        r#"{"data":{"nested":{"a":"a","b":"b"},"a":"a"}}"#,
+        run("{ nested { a } a ... on TestType { nested { b } } ...F } fragment F on TestType { a nested { a } }")
+    );
+    assert_eq!(r#"{"data":{"x":"a","a":"a"}}"#, run("{ x: a a x: a }"));
+    // The selection that's kept decides the field's place in the response.
+    assert_eq!(r#"{"data":{"b":"b","a":"a"}}"#, run("{ a @skip(if: true) b a }"));
+}
@@ -0,0 +1,7 @@
+pub mod abstract_types;
+pub mod async_resolvers;
+pub mod arguments;
+pub mod directives;
+pub mod mutations;
+pub mod nonnull;
+pub mod variables;
@@ -0,0 +1,137 @@
+use graphql::types::definition::*;
+use graphql::request::{Context, Request};
+use graphql::value::{Value, Map};
+use graphql::GraphQL;
+
+use std::sync::Mutex;
+
+/// The number the mutations change, kept in the request's context.
+struct Root {
+    number: Mutex<i64>,
+    /// The numbers mutations were asked to set, in the order they ran.
+    log: Mutex<Vec<i64>>
+}
+
+fn root(context: &Context) -> &Root {
+    context.get::<Root>().unwrap()
+}
+
+fn number_holder(number: i64) -> Value {
+    let mut holder = Map::new();
+    holder.insert("theNumber".to_owned(), number.into());
+    Value::Object(holder)
+}
+
+fn mutation_field(name: &str, field_type: TypeRef, fails: bool) -> Field {
+    let mut field = Field::new(name, field_type);
+    field.add_argument(InputValue::new("newNumber", TypeRef::named("Int")));
+    field.set_resolver(move |info| {
+        let root = root(info.context);
+        let new_number = info.arguments.get::<i64>("newNumber")?;
+        root.log.lock().unwrap().push(new_number);
+        if fails {
+            return Err("Cannot change the number".into());
+        }
+        *root.number.lock().unwrap() = new_number;
+        Ok(number_holder(new_number))
+    });
+    field
+}
+
+fn schema() -> Schema {
+    let mut holder = Object::new("NumberHolder");
+    holder.add_field(Field::new("theNumber", TypeRef::named("Int")));
+
+    let mut query = Object::new("Query");
+    let mut number_holder_field = Field::new("numberHolder", TypeRef::named("NumberHolder"));
+    number_holder_field.set_resolver(|info| Ok(number_holder(*root(info.context).number.lock().unwrap())));
+    query.add_field(number_holder_field);
+
+    let mut mutation = Object::new("Mutation");
+    mutation.add_field(mutation_field("immediatelyChangeTheNumber", TypeRef::named("NumberHolder"), false));
+    mutation.add_field(mutation_field("failToChangeTheNumber", TypeRef::named("NumberHolder"), true));
+    mutation.add_field(mutation_field("failToChangeTheNumberNonNull", TypeRef::non_null(TypeRef::named("NumberHolder")), true));
+
+    let mut schema = Schema::new(query);
+    schema.set_mutation(mutation);
+    schema.add_type(holder);
+    schema
+}
+
+/// Runs `query` starting from the number 6, returning the response along
+/// with the numbers the mutations were asked for, in order.
+fn run(query: &str) -> (String, Vec<i64>) {
+    let mut request = Request::new(query);
+    request.context.set(Root { number: Mutex::new(6), log: Mutex::new(vec![]) });
+    let response = GraphQL::execute(&schema(), &request).to_json();
+    let log = root(&request.context).log.lock().unwrap().clone();
+    (response, log)
+}
+
+#[test]
+fn it_evaluates_mutations_serially() {
+    let query = "
+      mutation M {
+        first: immediatelyChangeTheNumber(newNumber: 1) { theNumber }
+        second: immediatelyChangeTheNumber(newNumber: 2) { theNumber }
+        third: immediatelyChangeTheNumber(newNumber: 3) { theNumber }
+        fourth: immediatelyChangeTheNumber(newNumber: 4) { theNumber }
+        fifth: immediatelyChangeTheNumber(newNumber: 5) { theNumber }
+      }
+    ";
+    assert_eq!(
+        (concat!(r#"{"data":{"first":{"theNumber":1},"second":{"theNumber":2},"third":{"theNumber":3},"#,
+                 r#""fourth":{"theNumber":4},"fifth":{"theNumber":5}}}"#).to_owned(),
+         vec![1, 2, 3, 4, 5]),
+        run(query)
+    );
+}
+
+#[test]
+fn it_evaluates_mutations_correctly_in_the_presence_of_a_failed_mutation() {
+    let query = "
+      mutation M {
+        first: immediatelyChangeTheNumber(newNumber: 1) { theNumber }
+        second: failToChangeTheNumber(newNumber: 2) { theNumber }
+        third: immediatelyChangeTheNumber(newNumber: 3) { theNumber }
+      }
+    ";
+    assert_eq!(
+        (concat!(r#"{"errors":[{"message":"Cannot change the number","locations":[{"line":4,"column":9}],"path":["second"]}],"#,
+                 r#""data":{"first":{"theNumber":1},"second":null,"third":{"theNumber":3}}}"#).to_owned(),
+         vec![1, 2, 3]),
+        run(query)
+    );
+}
+
+#[test]
+fn it_stops_after_a_failed_non_null_mutation() {
+    let query = "
+      mutation M {
+        first: immediatelyChangeTheNumber(newNumber: 1) { theNumber }
+        second: failToChangeTheNumberNonNull(newNumber: 2) { theNumber }
+        third: immediatelyChangeTheNumber(newNumber: 3) { theNumber }
+      }
+    ";
+    assert_eq!(
+        (r#"{"errors":[{"message":"Cannot change the number","locations":[{"line":4,"column":9}],"path":["second"]}],"data":null}"#.to_owned(),
+         vec![1, 2]),
+        run(query)
+    );
+}
+
+#[test]
+fn it_executes_queries_against_the_query_root() {
+    assert_eq!(
+        (r#"{"data":{"numberHolder":{"theNumber":6}}}"#.to_owned(), vec![]),
+        run("{ numberHolder { theNumber } }")
+    );
+}
+
+#[test]
+fn it_rejects_subscriptions_without_a_subscription_root() {
+    assert_eq!(
+        (r#"{"errors":[{"message":"Schema is not configured for subscriptions."}]}"#.to_owned(), vec![]),
+        run("subscription { numberHolder { theNumber } }")
+    );
+}
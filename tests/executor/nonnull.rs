@@ -0,0 +1,318 @@
+use graphql::types::definition::*;
+use graphql::request::Request;
+use graphql::value::{Value, Map};
+use graphql::GraphQL;
+
+use super::async_resolvers::YieldOnce;
+
+/// Whether leaf fields fail or resolve to null.
+enum Mode {
+    Throwing,
+    Nulling
+}
+
+fn schema() -> Schema {
+    let mut data = Object::new("DataType");
+    for (name, field_type) in [("sync", TypeRef::named("String")), ("syncNonNull", TypeRef::non_null(TypeRef::named("String")))] {
+        let mut field = Field::new(name, field_type);
+        field.set_resolver(move |info| match *info.context.get::<Mode>().unwrap() {
+            Mode::Throwing => Err(name.into()),
+            Mode::Nulling => Ok(Value::Null)
+        });
+        data.add_field(field);
+    }
+    for (name, field_type) in [("promise", TypeRef::named("String")), ("promiseNonNull", TypeRef::non_null(TypeRef::named("String")))] {
+        let mut field = Field::new(name, field_type);
+        field.set_async_resolver(move |info| Box::pin(async move {
+            YieldOnce(false).await;
+            match *info.context.get::<Mode>().unwrap() {
+                Mode::Throwing => Err(name.into()),
+                Mode::Nulling => Ok(Value::Null)
+            }
+        }));
+        data.add_field(field);
+    }
+    for (name, field_type) in [("syncNest", TypeRef::named("DataType")), ("syncNonNullNest", TypeRef::non_null(TypeRef::named("DataType")))] {
+        let mut field = Field::new(name, field_type);
+        field.set_resolver(|_| Ok(Value::Object(Map::new())));
+        data.add_field(field);
+    }
+    for (name, field_type) in [("promiseNest", TypeRef::named("DataType")), ("promiseNonNullNest", TypeRef::non_null(TypeRef::named("DataType")))] {
+        let mut field = Field::new(name, field_type);
+        field.set_async_resolver(|_| Box::pin(async {
+            YieldOnce(false).await;
+            Ok(Value::Object(Map::new()))
+        }));
+        data.add_field(field);
+    }
+    for (name, field_type) in [
+        ("list", TypeRef::list(TypeRef::named("String"))),
+        ("listOfNonNull", TypeRef::list(TypeRef::non_null(TypeRef::named("String")))),
+        ("nonNullList", TypeRef::non_null(TypeRef::list(TypeRef::named("String")))),
+        ("nonNullListOfNonNull", TypeRef::non_null(TypeRef::list(TypeRef::non_null(TypeRef::named("String")))))
+    ] {
+        let mut field = Field::new(name, field_type);
+        field.set_resolver(|_| Ok(vec![Some("a"), None, Some("b")].into()));
+        data.add_field(field);
+    }
+    Schema::new(data)
+}
+
+fn run(query: &str, mode: Mode) -> String {
+    let mut request = Request::new(query);
+    request.context.set(mode);
+    GraphQL::execute(&schema(), &request).to_json()
+}
+
+#[test]
+fn it_nulls_a_nullable_field_that_throws() {
+    let query = "
+      query Q {
+        sync
+      }
+    ";
+    assert_eq!(
+        r#"{"errors":[{"message":"sync","locations":[{"line":3,"column":9}],"path":["sync"]}],"data":{"sync":null}}"#,
+        run(query, Mode::Throwing)
+    );
+    assert_eq!(r#"{"data":{"sync":null}}"#, run(query, Mode::Nulling));
+}
+
+#[test]
+fn it_nulls_an_object_that_contains_a_non_null_field_that_throws() {
+    let query = "
+      query Q {
+        syncNest {
+          syncNonNull,
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"syncNonNull","locations":[{"line":4,"column":11}],"path":["syncNest","syncNonNull"]}],"#,
+                r#""data":{"syncNest":null}}"#),
+        run(query, Mode::Throwing)
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot return null for non-nullable field DataType.syncNonNull.","#,
+                r#""locations":[{"line":4,"column":11}],"path":["syncNest","syncNonNull"]}],"data":{"syncNest":null}}"#),
+        run(query, Mode::Nulling)
+    );
+}
+
+#[test]
+fn it_nulls_a_complex_tree_of_nullable_fields_each() {
+    let query = "
+      query Q {
+        syncNest {
+          sync
+          syncNest {
+            sync
+          }
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"sync","locations":[{"line":4,"column":11}],"path":["syncNest","sync"]},"#,
+                r#"{"message":"sync","locations":[{"line":6,"column":13}],"path":["syncNest","syncNest","sync"]}],"#,
+                r#""data":{"syncNest":{"sync":null,"syncNest":{"sync":null}}}}"#),
+        run(query, Mode::Throwing)
+    );
+}
+
+#[test]
+fn it_nulls_the_first_nullable_object_after_a_long_chain_of_non_null_fields() {
+    let query = "
+      query Q {
+        syncNest {
+          syncNonNullNest {
+            syncNonNullNest {
+              syncNonNull
+            }
+          }
+        }
+        anotherNest: syncNest {
+          syncNonNullNest {
+            sync
+          }
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"syncNonNull","locations":[{"line":6,"column":15}],"path":["syncNest","syncNonNullNest","syncNonNullNest","syncNonNull"]},"#,
+                r#"{"message":"sync","locations":[{"line":12,"column":13}],"path":["anotherNest","syncNonNullNest","sync"]}],"#,
+                r#""data":{"syncNest":null,"anotherNest":{"syncNonNullNest":{"sync":null}}}}"#),
+        run(query, Mode::Throwing)
+    );
+}
+
+#[test]
+fn it_nulls_the_top_level_if_a_non_nullable_field_fails() {
+    let query = "
+      query Q { syncNonNull }
+    ";
+    assert_eq!(
+        r#"{"errors":[{"message":"syncNonNull","locations":[{"line":2,"column":17}],"path":["syncNonNull"]}],"data":null}"#,
+        run(query, Mode::Throwing)
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot return null for non-nullable field DataType.syncNonNull.","#,
+                r#""locations":[{"line":2,"column":17}],"path":["syncNonNull"]}],"data":null}"#),
+        run(query, Mode::Nulling)
+    );
+}
+
+#[test]
+fn it_propagates_null_list_items_to_the_nearest_nullable_position() {
+    assert_eq!(r#"{"data":{"list":["a",null,"b"]}}"#, run("{ list }", Mode::Nulling));
+    assert_eq!(r#"{"data":{"nonNullList":["a",null,"b"]}}"#, run("{ nonNullList }", Mode::Nulling));
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot return null for non-nullable field DataType.listOfNonNull.","#,
+                r#""locations":[{"line":1,"column":3}],"path":["listOfNonNull",1]}],"data":{"listOfNonNull":null}}"#),
+        run("{ listOfNonNull }", Mode::Nulling)
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot return null for non-nullable field DataType.nonNullListOfNonNull.","#,
+                r#""locations":[{"line":1,"column":20}],"path":["nest","nonNullListOfNonNull",1]}],"data":{"nest":null}}"#),
+        run("{ nest: syncNest { nonNullListOfNonNull } }", Mode::Nulling)
+    );
+}
+
+#[test]
+fn it_nulls_a_nullable_field_whose_future_fails() {
+    let query = "
+      query Q {
+        promise
+      }
+    ";
+    assert_eq!(
+        r#"{"errors":[{"message":"promise","locations":[{"line":3,"column":9}],"path":["promise"]}],"data":{"promise":null}}"#,
+        run(query, Mode::Throwing)
+    );
+    assert_eq!(r#"{"data":{"promise":null}}"#, run(query, Mode::Nulling));
+}
+
+#[test]
+fn it_nulls_an_object_that_contains_a_non_null_field_whose_future_fails() {
+    let query = "
+      query Q {
+        promiseNest {
+          promiseNonNull,
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"promiseNonNull","locations":[{"line":4,"column":11}],"path":["promiseNest","promiseNonNull"]}],"#,
+                r#""data":{"promiseNest":null}}"#),
+        run(query, Mode::Throwing)
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot return null for non-nullable field DataType.promiseNonNull.","#,
+                r#""locations":[{"line":4,"column":11}],"path":["promiseNest","promiseNonNull"]}],"data":{"promiseNest":null}}"#),
+        run(query, Mode::Nulling)
+    );
+}
+
+#[test]
+fn it_nulls_a_complex_tree_of_nullable_fields_with_futures_each() {
+    let query = "
+      query Q {
+        syncNest {
+          sync
+          promise
+          promiseNest {
+            sync
+            promise
+          }
+        }
+        promiseNest {
+          sync
+          promise
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"sync","locations":[{"line":4,"column":11}],"path":["syncNest","sync"]},"#,
+                r#"{"message":"promise","locations":[{"line":5,"column":11}],"path":["syncNest","promise"]},"#,
+                r#"{"message":"sync","locations":[{"line":7,"column":13}],"path":["syncNest","promiseNest","sync"]},"#,
+                r#"{"message":"sync","locations":[{"line":12,"column":11}],"path":["promiseNest","sync"]},"#,
+                r#"{"message":"promise","locations":[{"line":8,"column":13}],"path":["syncNest","promiseNest","promise"]},"#,
+                r#"{"message":"promise","locations":[{"line":13,"column":11}],"path":["promiseNest","promise"]}],"#,
+                r#""data":{"syncNest":{"sync":null,"promise":null,"promiseNest":{"sync":null,"promise":null}},"promiseNest":{"sync":null,"promise":null}}}"#),
+        run(query, Mode::Throwing)
+    );
+}
+
+#[test]
+fn it_nulls_the_first_nullable_object_after_a_long_chain_of_non_null_fields_with_futures() {
+    let query = "
+      query Q {
+        syncNest {
+          syncNonNullNest {
+            promiseNonNullNest {
+              syncNonNull
+            }
+          }
+        }
+        promiseNest {
+          syncNonNullNest {
+            promiseNonNullNest {
+              promiseNonNull
+            }
+          }
+        }
+        anotherNest: promiseNest {
+          promiseNonNullNest {
+            promise
+          }
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"syncNonNull","locations":[{"line":6,"column":15}],"path":["syncNest","syncNonNullNest","promiseNonNullNest","syncNonNull"]},"#,
+                r#"{"message":"promiseNonNull","locations":[{"line":13,"column":15}],"path":["promiseNest","syncNonNullNest","promiseNonNullNest","promiseNonNull"]},"#,
+                r#"{"message":"promise","locations":[{"line":19,"column":13}],"path":["anotherNest","promiseNonNullNest","promise"]}],"#,
+                r#""data":{"syncNest":null,"promiseNest":null,"anotherNest":{"promiseNonNullNest":{"promise":null}}}}"#),
+        run(query, Mode::Throwing)
+    );
+}
+
+#[test]
+fn it_nulls_the_top_level_if_a_non_nullable_future_fails() {
+    let query = "
+      query Q { promiseNonNull }
+    ";
+    assert_eq!(
+        r#"{"errors":[{"message":"promiseNonNull","locations":[{"line":2,"column":17}],"path":["promiseNonNull"]}],"data":null}"#,
+        run(query, Mode::Throwing)
+    );
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot return null for non-nullable field DataType.promiseNonNull.","#,
+                r#""locations":[{"line":2,"column":17}],"path":["promiseNonNull"]}],"data":null}"#),
+        run(query, Mode::Nulling)
+    );
+}
+
+#[test]
+fn it_reports_the_errors_of_futures_still_running_when_their_parent_is_nulled() {
+    let query = "
+      query Q {
+        promiseNest {
+          promiseNonNull
+          promiseNest {
+            promise
+          }
+        }
+      }
+    ";
+    assert_eq!(
+        concat!(r#"{"errors":["#,
+                r#"{"message":"promiseNonNull","locations":[{"line":4,"column":11}],"path":["promiseNest","promiseNonNull"]},"#,
+                r#"{"message":"promise","locations":[{"line":6,"column":13}],"path":["promiseNest","promiseNest","promise"]}],"#,
+                r#""data":{"promiseNest":null}}"#),
+        run(query, Mode::Throwing)
+    );
+}
@@ -0,0 +1,113 @@
+use graphql::types::definition::*;
+use graphql::request::{Request, Variables};
+use graphql::value::Value;
+use graphql::GraphQL;
+
+fn schema() -> Schema {
+    let mut test_input = InputObject::new("TestInputObject");
+    test_input.add_field(InputValue::new("a", TypeRef::named("String")));
+    test_input.add_field(InputValue::new("b", TypeRef::list(TypeRef::named("String"))));
+    test_input.add_field(InputValue::new("c", TypeRef::non_null(TypeRef::named("String"))));
+    let mut d = InputValue::new("d", TypeRef::named("Int"));
+    d.set_default_value(Value::Int(42));
+    test_input.add_field(d);
+
+    // Echoes the coerced value of the variable named like the field.
+    let mut query = Object::new("TestType");
+    for name in &["input", "count", "list", "color"] {
+        let mut field = Field::new(name, TypeRef::named("String"));
+        field.set_resolver(|info| {
+            Ok(info.variables.get(info.field_name).map_or(Value::Null, |value| Value::String(value.to_json())))
+        });
+        query.add_field(field);
+    }
+
+    let mut schema = Schema::new(query);
+    schema.add_type(test_input);
+    schema.add_type(Enum {
+        name: "Color".to_owned(),
+        description: String::new(),
+        values: vec![("RED".to_owned(), EnumValue { value: 0, description: String::new() })].into_iter().collect()
+    });
+    schema
+}
+
+fn run(query: &str, variables: Vec<(&str, Value)>) -> String {
+    let mut request = Request::new(query);
+    request.variables = variables.into_iter().map(|(name, value)| (name.to_owned(), value)).collect::<Variables>();
+    GraphQL::execute(&schema(), &request).to_json()
+}
+
+fn object(fields: Vec<(&str, Value)>) -> Value {
+    Value::Object(fields.into_iter().map(|(name, value)| (name.to_owned(), value)).collect())
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_owned())
+}
+
+#[test]
+fn it_coerces_input_objects_and_applies_field_defaults() {
+    let query = "query ($input: TestInputObject) { input }";
+    assert_eq!(
+        r#"{"data":{"input":"{\"a\":\"foo\",\"b\":[\"bar\"],\"c\":\"baz\",\"d\":42}"}}"#,
+        run(query, vec![("input", object(vec![("a", string("foo")), ("b", string("bar")), ("c", string("baz"))]))])
+    );
+}
+
+#[test]
+fn it_applies_variable_defaults() {
+    let query = "query ($count: Int = 3, $color: Color = RED, $list: [Int]) { count color list }";
+    assert_eq!(r#"{"data":{"count":"3","color":"\"RED\"","list":null}}"#, run(query, vec![]));
+    assert_eq!(r#"{"data":{"count":"null","color":"\"RED\"","list":null}}"#, run(query, vec![("count", Value::Null)]));
+}
+
+#[test]
+fn it_promotes_single_items_to_lists() {
+    let query = "query ($list: [Int]) { list }";
+    assert_eq!(r#"{"data":{"list":"[1]"}}"#, run(query, vec![("list", Value::Int(1))]));
+    assert_eq!(r#"{"data":{"list":"[1,null]"}}"#, run(query, vec![("list", Value::List(vec![Value::Int(1), Value::Null]))]));
+}
+
+#[test]
+fn it_reports_missing_and_null_required_variables() {
+    let query = "query ($count: Int!) { count }";
+    assert_eq!(
+        r#"{"errors":[{"message":"Variable \"$count\" of required type \"Int!\" was not provided.","locations":[{"line":1,"column":8}]}]}"#,
+        run(query, vec![])
+    );
+    assert_eq!(
+        r#"{"errors":[{"message":"Variable \"$count\" of non-null type \"Int!\" must not be null.","locations":[{"line":1,"column":8}]}]}"#,
+        run(query, vec![("count", Value::Null)])
+    );
+}
+
+#[test]
+fn it_reports_invalid_values_with_their_path() {
+    let query = "query ($input: TestInputObject, $list: [Int!], $color: Color) { input }";
+    let input = object(vec![("a", Value::Int(1)), ("b", Value::List(vec![string("x"), Value::Boolean(true)])), ("e", Value::Null)]);
+    let expected = vec![
+        r#"{"errors":["#,
+        r#"{"message":"Variable \"$input\" got invalid value 1 at \"input.a\"; Expected type \"String\".","locations":[{"line":1,"column":8}]},"#,
+        r#"{"message":"Variable \"$input\" got invalid value true at \"input.b[1]\"; Expected type \"String\".","locations":[{"line":1,"column":8}]},"#,
+        r#"{"message":"Variable \"$input\" got invalid value {\"a\":1,\"b\":[\"x\",true],\"e\":null}; "#,
+        r#"Field \"c\" of required type \"String!\" was not provided.","locations":[{"line":1,"column":8}]},"#,
+        r#"{"message":"Variable \"$input\" got invalid value {\"a\":1,\"b\":[\"x\",true],\"e\":null}; "#,
+        r#"Field \"e\" is not defined by type \"TestInputObject\".","locations":[{"line":1,"column":8}]},"#,
+        r#"{"message":"Variable \"$list\" got invalid value null at \"list[0]\"; Expected non-nullable type \"Int!\" not to be null.","locations":[{"line":1,"column":33}]},"#,
+        r#"{"message":"Variable \"$color\" got invalid value \"BLUE\"; Expected type \"Color\".","locations":[{"line":1,"column":48}]}"#,
+        r#"]}"#
+    ];
+    assert_eq!(
+        expected.concat(),
+        run(query, vec![("input", input), ("list", Value::List(vec![Value::Null])), ("color", string("BLUE"))])
+    );
+}
+
+#[test]
+fn it_rejects_variables_of_output_types() {
+    assert_eq!(
+        r#"{"errors":[{"message":"Variable \"$t\" expected value of type \"TestType\" which cannot be used as an input type.","locations":[{"line":1,"column":8}]}]}"#,
+        run("query ($t: TestType) { input }", vec![])
+    );
+}
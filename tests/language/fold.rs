@@ -0,0 +1,138 @@
+use graphql::language::parser::*;
+use graphql::language::lexer::*;
+use graphql::language::ast::*;
+use graphql::language::fold::*;
+
+fn parse(query: &str) -> Document {
+    Parser::parse(Source::new(query), ParseOptions::no_location()).unwrap()
+}
+
+fn field_names(selection_set: &SelectionSet) -> Vec<String> {
+    selection_set.selections.iter().filter_map(|s| match *s {
+        Selection::Field { ref name, .. } => Some(name.value.clone()),
+        _ => None
+    }).collect()
+}
+
+fn root(document: &Document) -> &SelectionSet {
+    match document.definitions[0] {
+        Definition::Operation { ref selection_set, .. } => selection_set,
+        Definition::Fragment { ref selection_set, .. } => selection_set,
+        _ => panic!("expected an executable definition")
+    }
+}
+
+fn child(selection_set: &SelectionSet, index: usize) -> &SelectionSet {
+    match selection_set.selections[index] {
+        Selection::Field { selection_set: Some(ref selection_set), .. } => selection_set,
+        Selection::InlineFragment { ref selection_set, .. } => selection_set,
+        _ => panic!("expected a selection set")
+    }
+}
+
+struct Identity;
+impl Fold for Identity {}
+
+#[test]
+fn it_rebuilds_documents_unchanged() {
+    let query = "query Q($a: [Int!] = [1]) @d { a: b(c: {d: $a}) { ...F ... on T @skip(if: true) { e } } }";
+    let document = Parser::parse(Source::new(query), ParseOptions::new()).unwrap();
+    let expected = Parser::parse(Source::new(query), ParseOptions::new()).unwrap();
+    assert_eq!(expected, Identity.fold_document(document));
+}
+
+#[test]
+fn it_adds_typename() {
+    let document = parse("
+{ hero { name friends { __typename name } ... on Droid { primaryFunction } } }
+fragment F on Character { id }
+");
+    let document = AddTypename::new().fold_document(document);
+
+    let operation = root(&document);
+    assert_eq!(vec!["hero"], field_names(operation));
+    let hero = child(operation, 0);
+    assert_eq!(vec!["name", "friends", "__typename"], field_names(hero));
+    assert_eq!(vec!["__typename", "name"], field_names(child(hero, 1)));
+    assert_eq!(vec!["primaryFunction", "__typename"], field_names(child(hero, 2)));
+
+    match document.definitions[1] {
+        Definition::Fragment { ref selection_set, .. } => {
+            assert_eq!(vec!["id", "__typename"], field_names(selection_set));
+            match selection_set.selections[1] {
+                Selection::Field { ref loc, .. } => assert_eq!(&None, loc),
+                _ => unreachable!()
+            }
+        },
+        _ => panic!("expected a fragment")
+    }
+}
+
+#[test]
+fn it_treats_inline_fragments_on_the_root_as_the_root() {
+    let document = parse("{ ... on Query { a ... @include(if: true) { b } } c { ... on T { d } } }");
+    let document = AddTypename::new().fold_document(document);
+
+    let operation = root(&document);
+    assert_eq!(vec!["c"], field_names(operation));
+    let fragment = child(operation, 0);
+    assert_eq!(vec!["a"], field_names(fragment));
+    assert_eq!(vec!["b"], field_names(child(fragment, 1)));
+    let c = child(operation, 1);
+    assert_eq!(vec!["__typename"], field_names(c));
+    assert_eq!(vec!["d", "__typename"], field_names(child(c, 0)));
+}
+
+#[test]
+fn it_removes_directives() {
+    let document = parse("{ a @client b @include(if: true) @client }");
+    let document = RemoveDirectives::new(&["client"]).fold_document(document);
+
+    for selection in &root(&document).selections {
+        match *selection {
+            Selection::Field { ref directives, .. } => {
+                assert!(directives.iter().all(|d| d.name.value != "client"));
+            },
+            _ => unreachable!()
+        }
+    }
+    match root(&document).selections[1] {
+        Selection::Field { ref directives, .. } => assert_eq!(1, directives.len()),
+        _ => unreachable!()
+    }
+}
+
+struct RenameAndDrop;
+
+impl Fold for RenameAndDrop {
+    fn fold_selection(&mut self, node: Selection) -> Vec<Selection> {
+        match node {
+            Selection::Field { ref name, .. } if name.value == "legacy" => vec![],
+            Selection::InlineFragment { selection_set, .. } => {
+                fold_selection_set(self, selection_set).selections
+            },
+            node => fold_selection(self, node)
+        }
+    }
+
+    fn fold_name(&mut self, mut node: Name) -> Name {
+        if node.value == "oldName" {
+            node.value = "newName".to_owned();
+        }
+        node
+    }
+}
+
+#[test]
+fn it_replaces_removes_and_inserts_nodes() {
+    let source = "{ oldName legacy ... on User { id legacy } }";
+    let document = Parser::parse(Source::new(source), ParseOptions::no_source()).unwrap();
+    let document = RenameAndDrop.fold_document(document);
+
+    let selections = root(&document);
+    assert_eq!(vec!["newName", "id"], field_names(selections));
+    match selections.selections[1] {
+        Selection::Field { ref loc, .. } => assert_eq!(&Some(Location { start: 31, end: 33, source: None }), loc),
+        _ => unreachable!()
+    }
+}
@@ -0,0 +1,118 @@
+use graphql::language::parser::*;
+use graphql::language::lexer::*;
+use graphql::language::ast::*;
+use graphql::language::json::*;
+
+use serde_json;
+
+use std::io::Read;
+use std::fs::File;
+
+fn parse(query: &str) -> Document {
+    Parser::parse(Source::new(query), ParseOptions::no_source()).unwrap()
+}
+
+fn read(path: &str) -> String {
+    let mut contents = String::new();
+    File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+    contents
+}
+
+// graphql-js: JSON.stringify(parse("{ a(b: 1) @c }"))
+const GRAPHQL_JS: &str = r#"{
+  "kind": "Document",
+  "definitions": [{
+    "kind": "OperationDefinition",
+    "operation": "query",
+    "variableDefinitions": [],
+    "directives": [],
+    "selectionSet": {
+      "kind": "SelectionSet",
+      "selections": [{
+        "kind": "Field",
+        "name": { "kind": "Name", "value": "a", "loc": { "start": 2, "end": 3 } },
+        "arguments": [{
+          "kind": "Argument",
+          "name": { "kind": "Name", "value": "b", "loc": { "start": 4, "end": 5 } },
+          "value": { "kind": "IntValue", "value": "1", "loc": { "start": 7, "end": 8 } },
+          "loc": { "start": 4, "end": 8 }
+        }],
+        "directives": [{
+          "kind": "Directive",
+          "name": { "kind": "Name", "value": "c", "loc": { "start": 11, "end": 12 } },
+          "arguments": [],
+          "loc": { "start": 10, "end": 12 }
+        }],
+        "loc": { "start": 2, "end": 12 }
+      }],
+      "loc": { "start": 0, "end": 14 }
+    },
+    "loc": { "start": 0, "end": 14 }
+  }],
+  "loc": { "start": 0, "end": 14 }
+}"#;
+
+#[test]
+fn it_matches_graphql_js() {
+    let expected: serde_json::Value = serde_json::from_str(GRAPHQL_JS).unwrap();
+    assert_eq!(expected, to_json(&parse("{ a(b: 1) @c }")));
+    assert_eq!(parse("{ a(b: 1) @c }"), from_json(&expected).unwrap());
+}
+
+#[test]
+fn it_orders_keys_like_graphql_js() {
+    let expected: serde_json::Value = serde_json::from_str(GRAPHQL_JS).unwrap();
+    let json = serde_json::to_string(&to_json(&parse("{ a(b: 1) @c }"))).unwrap();
+    assert!(json.starts_with(r#"{"kind":"Document","definitions":[{"kind":"OperationDefinition","operation":"query","#));
+    assert_eq!(serde_json::to_string(&expected).unwrap(), json);
+}
+
+#[test]
+fn it_serializes_values() {
+    let document = parse("query ($a: [In!] = {b: null, c: [1.5, \"\"\"d\"\"\", true, E]}) { f(g: $a) }");
+    let json = to_json(&document);
+    let default_value = &json["definitions"][0]["variableDefinitions"][0]["defaultValue"];
+    assert_eq!("ObjectValue", default_value["kind"]);
+    assert_eq!("NullValue", default_value["fields"][0]["value"]["kind"]);
+    let list = &default_value["fields"][1]["value"];
+    assert_eq!("ListValue", list["kind"]);
+    assert_eq!("1.5", list["values"][0]["value"]);
+    assert_eq!(true, list["values"][1]["block"]);
+    assert_eq!(true, list["values"][2]["value"]);
+    assert_eq!("EnumValue", list["values"][3]["kind"]);
+
+    let var_type = &json["definitions"][0]["variableDefinitions"][0]["type"];
+    assert_eq!("ListType", var_type["kind"]);
+    assert_eq!("NonNullType", var_type["type"]["kind"]);
+    assert_eq!("In", var_type["type"]["type"]["name"]["value"]);
+}
+
+#[test]
+fn it_round_trips_through_serde() {
+    for query in &[read("tests/data/kitchen-sink.graphql"), read("tests/data/introspection.graphql")] {
+        let document = parse(query);
+        let text = serde_json::to_string(&document).unwrap();
+        let parsed: Document = serde_json::from_str(&text).unwrap();
+        assert_eq!(document, parsed);
+    }
+
+    let directive = parse("\"d\" directive @a(\"b\" b: Int = 1) repeatable on FIELD | QUERY");
+    assert_eq!(directive, from_json(&to_json(&directive)).unwrap());
+
+    let no_location = Parser::parse(Source::new("{ a }"), ParseOptions::no_location()).unwrap();
+    let json = to_json(&no_location);
+    assert!(json.get("loc").is_none());
+    assert_eq!(no_location, from_json(&json).unwrap());
+}
+
+#[test]
+fn it_rejects_malformed_json() {
+    let error = from_json(&serde_json::from_str(r#"{"kind": "Field"}"#).unwrap()).err().unwrap();
+    assert_eq!("Expected a Document node, found kind 'Field'", error.description);
+
+    let error = from_json(&serde_json::from_str(r#"{"kind": "Document"}"#).unwrap()).err().unwrap();
+    assert_eq!("Missing 'definitions' in Document node", error.description);
+
+    let error = serde_json::from_str::<Document>(r#"{"kind": "Document", "definitions": [{"kind": "Name"}]}"#).err().unwrap();
+    assert!(error.to_string().starts_with("Expected a OperationDefinition or FragmentDefinition or DirectiveDefinition node"));
+}
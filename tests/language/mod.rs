@@ -1 +1,8 @@
 pub mod parser;
+pub mod visitor;
+pub mod fold;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod printer;
+pub mod normalize;
+pub mod operation;
@@ -0,0 +1,91 @@
+use graphql::language::parser::*;
+use graphql::language::lexer::*;
+use graphql::language::ast::*;
+use graphql::language::normalize::*;
+
+fn parse(query: &str) -> Document {
+    Parser::parse(Source::new(query), ParseOptions::new()).unwrap()
+}
+
+fn signature_of(query: &str) -> String {
+    signature(parse(query), NormalizeOptions::new())
+}
+
+#[test]
+fn it_sorts_and_hides_literals() {
+    assert_eq!(
+        "query Q($a:Int$b:[String]=[]){a(x:\"\"y:0)b{c d(e:0 f:true)}...F...on T@include(if:$a)@skip(if:false){g}}fragment F on T{h(i:{}j:RED k:null)}",
+        signature_of("
+query Q($b: [String] = [\"one\"], $a: Int) {
+  ... on T @skip(if: false) @include(if: $a) { g }
+  b { d(f: true, e: 1.5) c }
+  ...F
+  a(y: 10, x: \"hello\")
+}
+fragment Unused on T { z }
+fragment F on T { h(k: null, j: RED, i: { a: 1 }) }
+")
+    );
+}
+
+#[test]
+fn it_groups_equivalent_operations() {
+    let options = NormalizeOptions::new();
+    let first = signature_hash(parse("query Hero { hero(episode: 4) { name id } }"), options);
+    let second = signature_hash(parse("query Hero {\n  hero(episode: 5) {\n    id,\n    name\n  }\n}"), options);
+    let other = signature_hash(parse("query Hero { hero(episode: 4) { name } }"), options);
+    assert_eq!(first, second);
+    assert!(first != other);
+}
+
+#[test]
+fn it_hashes_stably() {
+    assert_eq!(0xcbf2_9ce4_8422_2325, signature_hash(parse("fragment F on T { a }"), NormalizeOptions::new()));
+    assert_eq!(
+        signature_hash(parse("{ a }"), NormalizeOptions::new()),
+        signature_hash(parse("{ a }"), NormalizeOptions::new())
+    );
+    // FNV-1a of "{a}"
+    assert_eq!(0xc779_d619_8416_9054, signature_hash(parse("{ a }"), NormalizeOptions::new()));
+}
+
+#[test]
+fn it_drops_aliases_on_request() {
+    assert_eq!("{b:a}", signature_of("{ b: a }"));
+
+    let mut options = NormalizeOptions::new();
+    options.set_drop_aliases(true);
+    assert_eq!("{a}", signature(parse("{ b: a }"), options));
+}
+
+#[test]
+fn it_keeps_literals_on_request() {
+    let mut options = NormalizeOptions::new();
+    options.set_hide_literals(false);
+    assert_eq!("{a(b:[1\"c\"])}", signature(parse("{ a(b: [1, \"c\"]) }"), options));
+}
+
+#[test]
+fn it_keeps_fragments_used_by_fragments() {
+    assert_eq!(
+        "{...A}fragment A on T{...B}fragment B on T{a}",
+        signature_of("fragment C on T { c } fragment B on T { a } fragment A on T { ...B } { ...A }")
+    );
+}
+
+#[test]
+fn it_orders_selections_that_only_differ_in_their_details() {
+    let mut options = NormalizeOptions::new();
+    options.set_hide_literals(false);
+    let first = parse("{ a(x: 1) a(x: 2) { b } b @skip(if: true) b @include(if: true) ... on T { c } ... on T { a } }");
+    let second = parse("{ ... on T { a } ... on T { c } b @include(if: true) b @skip(if: true) a(x: 2) { b } a(x: 1) }");
+    assert_eq!(signature_hash(first, options), signature_hash(second, options));
+    assert_eq!(
+        "{a(x:1)a(x:2){b}b@include(if:true)b@skip(if:true)...on T{a}...on T{c}}",
+        signature(parse("{ ... on T { c } ... on T { a } b @skip(if: true) b @include(if: true) a(x: 2) { b } a(x: 1) }"), options)
+    );
+
+    let first = parse("{ a(x: 1) { b } a(x: 2) { c } }");
+    let second = parse("{ a(x: 2) { c } a(x: 1) { b } }");
+    assert_eq!(signature(first, NormalizeOptions::new()), signature(second, NormalizeOptions::new()));
+}
@@ -0,0 +1,73 @@
+use graphql::language::parser::*;
+use graphql::language::lexer::*;
+use graphql::language::ast::*;
+use graphql::language::operation::*;
+
+fn parse(query: &str) -> Document {
+    Parser::parse(Source::new(query), ParseOptions::no_location()).unwrap()
+}
+
+fn operation_name(definition: &Definition) -> Option<&str> {
+    match *definition {
+        Definition::Operation { ref name, .. } => name.as_ref().map(|name| &name.value[..]),
+        _ => panic!("expected an operation")
+    }
+}
+
+fn fragment_names(fragments: Vec<&Definition>) -> Vec<&str> {
+    fragments.into_iter().map(|fragment| match *fragment {
+        Definition::Fragment { ref name, .. } => &name.value[..],
+        _ => panic!("expected a fragment")
+    }).collect()
+}
+
+#[test]
+fn it_gets_the_only_operation() {
+    let document = parse("fragment F on T { a } { ...F }");
+    assert_eq!(None, operation_name(get_operation(&document, None).unwrap()));
+
+    let document = parse("query Q { a }");
+    assert_eq!(Some("Q"), operation_name(get_operation(&document, None).unwrap()));
+}
+
+#[test]
+fn it_gets_operations_by_name() {
+    let document = parse("query A { a } mutation B { b } subscription C { c }");
+    assert_eq!(Some("B"), operation_name(get_operation(&document, Some("B")).unwrap()));
+    assert_eq!(Some("C"), operation_name(get_operation(&document, Some("C")).unwrap()));
+
+    assert_eq!(
+        "Unknown operation named \"D\".",
+        get_operation(&document, Some("D")).err().unwrap().description
+    );
+}
+
+#[test]
+fn it_rejects_ambiguous_and_missing_operations() {
+    let document = parse("query A { a } query B { b }");
+    assert_eq!(
+        "Must provide operation name if query contains multiple operations.",
+        get_operation(&document, None).err().unwrap().description
+    );
+
+    let document = parse("fragment F on T { a }");
+    assert_eq!("Must provide an operation.", get_operation(&document, None).err().unwrap().description);
+}
+
+#[test]
+fn it_collects_fragments_transitively() {
+    let document = parse("
+query A { a { ...B ... on T { ...C } } }
+fragment Unused on T { z }
+fragment C on T { ...D ...B }
+fragment B on T { b }
+fragment D on T { d { ...C } }
+query Other { ...Unused }
+");
+    let operation = get_operation(&document, Some("A")).unwrap();
+    assert_eq!(vec!["C", "B", "D"], fragment_names(get_fragments(&document, operation).unwrap()));
+
+    let document = parse("{ ...Missing }");
+    let operation = get_operation(&document, None).unwrap();
+    assert_eq!("Unknown fragment \"Missing\".", get_fragments(&document, operation).err().unwrap().description);
+}
@@ -7,13 +7,11 @@ use graphql::language::lexer::*;
 use graphql::language::ast::*;
 use graphql::language::kinds::*;
 
-use self::env_logger::*;
-
 use std::io::Read;
 use std::fs::File;
 
 fn loc_builder(start: usize, end: usize, source: Option<Source>) -> Option<Location> {
-    Some(Location { start: start, end: end, source: source })
+    Some(Location { start, end, source })
 }
 
 macro_rules! parse_no_source {
@@ -29,19 +27,57 @@ macro_rules! parse_no_source {
 fn it_provides_useful_errors() {
     let mut document;
     document = parse_no_source!("notanoperation Foo { field }");
-    assert_eq!(true, document.is_err());
-    assert_eq!("Could not parse document, missing NameKind at location 0", document.err().unwrap().description);
+    assert!(document.is_err());
+    assert_eq!("Could not parse document, missing NameKind", document.err().unwrap().description);
 
     document = parse_no_source!("
 { ...MissingOn }
 fragment MissingOn Type
 ");
-    assert_eq!(true, document.is_err());
+    assert!(document.is_err());
     assert_eq!("Expected 'on' and got 'Type'", document.err().unwrap().description);
 
-    // TODO finish these
-    //document = parse_no_source!("{ field: {} }");
-    //assert_eq!(true, document.is_err());
+    document = parse_no_source!("{ field: {} }");
+    assert!(document.is_err());
+    assert_eq!("Expected Name, found BraceL", document.err().unwrap().description);
+
+    document = parse_no_source!("query Foo($a: Int = $b) { field }");
+    assert!(document.is_err());
+    assert_eq!("Unexpected variable in constant value", document.err().unwrap().description);
+
+    document = parse_no_source!("query Foo($a: [In] = [{ b: [1, $c] }]) { field }");
+    assert!(document.is_err());
+    assert_eq!("Unexpected variable in constant value", document.err().unwrap().description);
+
+    document = parse_no_source!("{ ...on }");
+    assert!(document.is_err());
+    assert_eq!("Expected Name, found BraceR", document.err().unwrap().description);
+
+    document = parse_no_source!("fragment on on Type { field }");
+    assert!(document.is_err());
+    assert_eq!("Unexpected Name 'on'", document.err().unwrap().description);
+
+    document = parse_no_source!("{ field(arg: \"unterminated) }");
+    assert!(document.is_err());
+    assert_eq!("Unterminated string", document.err().unwrap().description);
+
+    document = parse_no_source!("{ field(arg: { a: 1, a: 2 }) }");
+    assert!(document.is_err());
+    assert_eq!("Duplicate input object field a.", document.err().unwrap().description);
+}
+
+#[test]
+fn it_locates_errors() {
+    let error = parse_no_source!("{ ...on }").err().unwrap();
+    assert_eq!(Some(SourceLocation { line: 1, column: 9 }), error.location);
+
+    let error = parse_no_source!("{\n  field(arg: \"unterminated) }").err().unwrap();
+    assert_eq!(Some(SourceLocation { line: 2, column: 14 }), error.location);
+    assert_eq!("Unterminated string", error.description);
+
+    let error = parse_no_source!("{ a }\nnotanoperation { b }").err().unwrap();
+    assert_eq!(Some(SourceLocation { line: 2, column: 1 }), error.location);
+    assert_eq!("Could not parse document, missing NameKind", error.description);
 }
 
 #[test]
@@ -53,7 +89,7 @@ fn it_accepts_option_to_not_include_source() {
             Definition::Operation {
                 kind: Kinds::OperationDefinition,
                 loc: loc_builder(0, 9, None),
-                operation: "query".to_string(),
+                operation: OperationType::Query,
                 name: None,
                 variable_definitions: None,
                 directives: vec![],
@@ -85,10 +121,356 @@ fn it_accepts_option_to_not_include_source() {
     assert_eq!(goal, document.ok().unwrap());
 }
 
+#[test]
+fn it_parses_operation_types() {
+    let document = parse_no_source!("
+query Q { a }
+mutation M { b }
+subscription S($id: ID) { c(id: $id) }
+").unwrap();
+
+    let operations: Vec<OperationType> = document.definitions.iter().map(|definition| {
+        match *definition {
+            Definition::Operation { operation, .. } => operation,
+            _ => panic!("expected an operation")
+        }
+    }).collect();
+    assert_eq!(vec![OperationType::Query, OperationType::Mutation, OperationType::Subscription], operations);
+    assert_eq!("subscription", OperationType::Subscription.to_string());
+}
+
+#[test]
+fn it_parses_null_values() {
+    let document = Parser::parse(
+        Source::new("query Q($a: In = { b: null, c: [null] }) { field(arg: null) }"),
+        ParseOptions::no_location()
+    ).unwrap();
+
+    match document.definitions[0] {
+        Definition::Operation { ref variable_definitions, ref selection_set, .. } => {
+            let definitions = variable_definitions.as_ref().unwrap();
+            assert_eq!(definitions[0].default_value, Some(Value::ObjectValue {
+                kind: Kinds::Object,
+                fields: vec![
+                    ObjectField {
+                        kind: Kinds::ObjectField,
+                        name: Name { kind: Kinds::Name, value: "b".to_string(), loc: None },
+                        value: Value::NullValue { kind: Kinds::Null, loc: None },
+                        loc: None
+                    },
+                    ObjectField {
+                        kind: Kinds::ObjectField,
+                        name: Name { kind: Kinds::Name, value: "c".to_string(), loc: None },
+                        value: Value::ArrayValue {
+                            kind: Kinds::Array,
+                            values: vec![Value::NullValue { kind: Kinds::Null, loc: None }],
+                            loc: None
+                        },
+                        loc: None
+                    }
+                ],
+                loc: None
+            }));
+
+            match selection_set.selections[0] {
+                Selection::Field { ref arguments, .. } => {
+                    assert_eq!(arguments[0].value, Value::NullValue { kind: Kinds::Null, loc: None });
+                },
+                _ => panic!("expected a field")
+            }
+        },
+        _ => panic!("expected an operation")
+    }
+}
+
+fn name(value: &str) -> Name {
+    Name { kind: Kinds::Name, value: value.to_string(), loc: None }
+}
+
+#[test]
+fn it_parses_inline_fragments_without_type_conditions() {
+    let document = Parser::parse(Source::new("{ ... @include(if: $a) { b } ... { c } ... on T { d } }"), ParseOptions::no_location()).unwrap();
+    match document.definitions[0] {
+        Definition::Operation { ref selection_set, .. } => {
+            let conditions: Vec<bool> = selection_set.selections.iter().map(|selection| match *selection {
+                Selection::InlineFragment { ref type_condition, .. } => type_condition.is_some(),
+                _ => panic!("expected an inline fragment")
+            }).collect();
+            assert_eq!(vec![false, false, true], conditions);
+        },
+        _ => panic!("expected an operation")
+    }
+}
+
+#[test]
+fn it_parses_directives_on_variable_definitions() {
+    let document = Parser::parse(
+        Source::new("query Q($id: ID = 1 @deprecated(reason: \"old\") @tag) { node(id: $id) }"),
+        ParseOptions::no_location()
+    ).unwrap();
+
+    match document.definitions[0] {
+        Definition::Operation { ref variable_definitions, .. } => {
+            let definition = &variable_definitions.as_ref().unwrap()[0];
+            let names: Vec<&str> = definition.directives.iter().map(|d| d.name.value.as_str()).collect();
+            assert_eq!(vec!["deprecated", "tag"], names);
+        },
+        _ => panic!("expected an operation")
+    }
+
+    let document = parse_no_source!("query Q($id: ID @tag(name: $other)) { node }");
+    assert_eq!("Unexpected variable in constant value", document.err().unwrap().description);
+}
+
+#[test]
+fn it_parses_fragment_variables_when_enabled() {
+    let query = "fragment F($size: Int = 10) on User { pic(size: $size) }";
+
+    let document = parse_no_source!(query);
+    assert_eq!("Expected 'on' and got ''", document.err().unwrap().description);
+
+    let mut options = ParseOptions::no_location();
+    options.set_experimental_fragment_variables(true);
+    let document = Parser::parse(Source::new(query), options).unwrap();
+    match document.definitions[0] {
+        Definition::Fragment { ref variable_definitions, .. } => {
+            let definitions = variable_definitions.as_ref().unwrap();
+            assert_eq!(1, definitions.len());
+            assert_eq!(definitions[0].var_type, Type::Named { kind: Kinds::NamedType, name: name("Int"), loc: None });
+        },
+        _ => panic!("expected a fragment")
+    }
+
+    let document = Parser::parse(Source::new("fragment F on User { id }"), options).unwrap();
+    match document.definitions[0] {
+        Definition::Fragment { ref variable_definitions, .. } => assert_eq!(&None, variable_definitions),
+        _ => panic!("expected a fragment")
+    }
+}
+
+#[test]
+fn it_parses_directive_definitions() {
+    let document = Parser::parse(Source::new("
+\"\"\"
+Tags a field.
+\"\"\"
+directive @tag(\"the tag\" name: String! = \"none\") repeatable on | FIELD_DEFINITION | OBJECT
+
+directive @skip(if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+"), ParseOptions::no_location()).unwrap();
+
+    assert_eq!(document.definitions[0], Definition::Directive {
+        kind: Kinds::DirectiveDefinition,
+        description: Some(Value::StringValue {
+            kind: Kinds::String,
+            value: "Tags a field.".to_string(),
+            block: true,
+            loc: None
+        }),
+        name: name("tag"),
+        arguments: vec![
+            InputValueDefinition {
+                kind: Kinds::InputValueDefinition,
+                description: Some(Value::StringValue {
+                    kind: Kinds::String,
+                    value: "the tag".to_string(),
+                    block: false,
+                    loc: None
+                }),
+                name: name("name"),
+                value_type: Type::NonNull {
+                    kind: Kinds::NonNullType,
+                    t_type: Box::new(Type::Named { kind: Kinds::NamedType, name: name("String"), loc: None }),
+                    loc: None
+                },
+                default_value: Some(Value::StringValue {
+                    kind: Kinds::String,
+                    value: "none".to_string(),
+                    block: false,
+                    loc: None
+                }),
+                directives: vec![],
+                loc: None
+            }
+        ],
+        repeatable: true,
+        locations: vec![name("FIELD_DEFINITION"), name("OBJECT")],
+        loc: None
+    });
+
+    match document.definitions[1] {
+        Definition::Directive { ref name, repeatable, ref locations, .. } => {
+            assert_eq!("skip", name.value);
+            assert!(!repeatable);
+            assert_eq!(3, locations.len());
+        },
+        _ => panic!("expected a directive definition")
+    }
+
+    let document = parse_no_source!("directive @tag on FIELD | NOWHERE");
+    assert_eq!("Unexpected Name 'NOWHERE'", document.err().unwrap().description);
+}
+
+#[test]
+fn it_parses_standalone_values() {
+    let value = Parser::parse_value(Source::new("[123 \"abc\" $var]"), ParseOptions::no_location()).unwrap();
+    assert_eq!(value, Value::ArrayValue {
+        kind: Kinds::Array,
+        values: vec![
+            Value::IntValue { kind: Kinds::Int, value: "123".to_string(), loc: None },
+            Value::StringValue { kind: Kinds::String, value: "abc".to_string(), block: false, loc: None },
+            Value::VariableValue { kind: Kinds::Variable, name: name("var"), loc: None }
+        ],
+        loc: None
+    });
+
+    let value = Parser::parse_value(Source::new("{ a: 1 }"), ParseOptions::no_source()).unwrap();
+    match value {
+        Value::ObjectValue { loc, .. } => assert_eq!(loc_builder(0, 8, None), loc),
+        _ => panic!("expected an object")
+    }
+
+    let error = Parser::parse_value(Source::new("1 2"), ParseOptions::new()).err().unwrap();
+    assert_eq!("Expected EOF, found Int", error.description);
+}
+
+#[test]
+fn it_parses_standalone_const_values() {
+    let value = Parser::parse_const_value(Source::new("{ size: LARGE, tags: [\"a\"] }"), ParseOptions::no_location()).unwrap();
+    match value {
+        Value::ObjectValue { fields, .. } => assert_eq!(2, fields.len()),
+        _ => panic!("expected an object")
+    }
+
+    let error = Parser::parse_const_value(Source::new("[1, $var]"), ParseOptions::new()).err().unwrap();
+    assert_eq!("Unexpected variable in constant value", error.description);
+}
+
+#[test]
+fn it_parses_standalone_types() {
+    let _type = Parser::parse_type(Source::new("[String!]!"), ParseOptions::no_location()).unwrap();
+    assert_eq!(_type, Type::NonNull {
+        kind: Kinds::NonNullType,
+        t_type: Box::new(Type::List {
+            kind: Kinds::ListType,
+            t_type: Box::new(Type::NonNull {
+                kind: Kinds::NonNullType,
+                t_type: Box::new(Type::Named { kind: Kinds::NamedType, name: name("String"), loc: None }),
+                loc: None
+            }),
+            loc: None
+        }),
+        loc: None
+    });
+
+    let error = Parser::parse_type(Source::new("[String"), ParseOptions::new()).err().unwrap();
+    assert_eq!("Expected BracketR, found EOF", error.description);
+
+    let error = Parser::parse_type(Source::new("String!!"), ParseOptions::new()).err().unwrap();
+    assert_eq!("Expected EOF, found Bang", error.description);
+}
+
+#[test]
+fn it_enforces_the_token_limit() {
+    let mut options = ParseOptions::new();
+    options.set_max_tokens(Some(4));
+
+    assert!(Parser::parse(Source::new("{ a b }"), options).is_ok());
+
+    let error = Parser::parse(Source::new("{ a b c }"), options).err().unwrap();
+    assert_eq!("Document contains more than 4 tokens", error.description);
+}
+
+#[test]
+fn it_enforces_the_source_length_limit() {
+    let mut options = ParseOptions::new();
+    options.set_max_source_length(Some(9));
+
+    assert!(Parser::parse(Source::new("{ field }"), options).is_ok());
+
+    let error = Parser::parse(Source::new("{ field2 }"), options).err().unwrap();
+    assert_eq!("Document is 10 bytes long, exceeding the limit of 9 bytes", error.description);
+}
+
+#[test]
+fn it_enforces_the_depth_limit() {
+    let mut options = ParseOptions::new();
+    options.set_max_depth(Some(3));
+
+    assert!(Parser::parse(Source::new("{ a { b { c } } }"), options).is_ok());
+    assert!(Parser::parse(Source::new("{ a(x: [[1]]) }"), options).is_ok());
+
+    let error = Parser::parse(Source::new("{ a { b { c { d } } } }"), options).err().unwrap();
+    assert_eq!("Document exceeds the maximum nesting depth of 3", error.description);
+
+    let error = Parser::parse(Source::new("{ a { b(x: [{ y: 1 }]) } }"), options).err().unwrap();
+    assert_eq!("Document exceeds the maximum nesting depth of 3", error.description);
+
+    let error = Parser::parse_type(Source::new("[[[[Int]]]]"), options).err().unwrap();
+    assert_eq!("Document exceeds the maximum nesting depth of 3", error.description);
+}
+
+#[test]
+fn it_rejects_deeply_nested_input_without_overflowing() {
+    let mut options = ParseOptions::new();
+    options.set_max_depth(Some(64));
+
+    let depth = 100_000;
+    let value = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let error = Parser::parse_value(Source::from(value), options).err().unwrap();
+    assert_eq!("Document exceeds the maximum nesting depth of 64", error.description);
+
+    let selections = format!("{{{}{}}}", "a{".repeat(depth), "}".repeat(depth));
+    let error = Parser::parse(Source::from(selections), options).err().unwrap();
+    assert_eq!("Document exceeds the maximum nesting depth of 64", error.description);
+}
+
 #[test]
 fn it_parses_variable_inline_values() {
     let source = Source::new("{ field(complex: { a: { b: [ $var ] } }) }");
-    let _ = Parser::parse(source, ParseOptions::new());
+    assert!(Parser::parse(source, ParseOptions::new()).is_ok());
+}
+
+#[test]
+fn it_names_sources_in_errors() {
+    let mut source = Source::new("{ field(arg: \"unterminated) }");
+    source.set_name("queries/broken.graphql");
+    assert_eq!(Some("queries/broken.graphql"), source.name());
+
+    let error = Parser::parse(source.clone(), ParseOptions::new()).err().unwrap();
+    assert_eq!(Some("queries/broken.graphql".to_owned()), error.source_name);
+    assert_eq!("queries/broken.graphql: Unterminated string", error.to_string());
+
+    let error = Parser::parse_type(source, ParseOptions::new()).err().unwrap();
+    assert_eq!("queries/broken.graphql: Expected Name, found BraceL", error.to_string());
+
+    let error = Parser::parse(Source::new("{"), ParseOptions::new()).err().unwrap();
+    assert_eq!(None, error.source_name);
+    assert_eq!(error.description, error.to_string());
+}
+
+#[test]
+fn it_parses_many_sources_into_one_document() {
+    let mut query = Source::new("query Hero { hero { ...HeroName } }");
+    query.set_name("hero.graphql");
+    let mut fragment = Source::new("fragment HeroName on Character { name }");
+    fragment.set_name("fragments/hero_name.graphql");
+
+    let document = Parser::parse_all(vec![query, fragment], ParseOptions::new()).unwrap();
+    assert_eq!(None, document.loc);
+    assert_eq!(2, document.definitions.len());
+
+    let names: Vec<Option<&str>> = document.definitions.iter().map(|definition| match *definition {
+        Definition::Operation { loc: Some(ref loc), .. } |
+        Definition::Fragment { loc: Some(ref loc), .. } => loc.source.as_ref().unwrap().name(),
+        _ => panic!("expected a located definition")
+    }).collect();
+    assert_eq!(vec![Some("hero.graphql"), Some("fragments/hero_name.graphql")], names);
+
+    let mut broken = Source::new("fragment on on T { a }");
+    broken.set_name("broken.graphql");
+    let error = Parser::parse_all(vec![Source::new("{ a }"), broken], ParseOptions::new()).err().unwrap();
+    assert_eq!("broken.graphql: Unexpected Name 'on'", error.to_string());
 }
 
 #[test]
@@ -99,7 +481,17 @@ fn it_parses_the_kitchen_sink() {
     f.read_to_string(&mut s).unwrap();
 
     let source = Source::new(s.trim());
-    let _ = Parser::parse(source, ParseOptions::new());
+    assert!(Parser::parse(source, ParseOptions::new()).is_ok());
+}
+
+#[test]
+fn it_parses_the_introspection_query() {
+    let mut f = File::open("tests/data/introspection.graphql").unwrap();
+    let mut s = String::new();
+    f.read_to_string(&mut s).unwrap();
+
+    let document = Parser::parse(Source::from(s), ParseOptions::no_location()).unwrap();
+    assert_eq!(4, document.definitions.len());
 }
 
 #[test]
@@ -122,7 +514,7 @@ fn it_parsers_creates_ast() {
             Definition::Operation {
                 kind: Kinds::OperationDefinition,
                 loc: loc_builder(1, 53, Some(source.clone())),
-                operation: "query".to_string(),
+                operation: OperationType::Query,
                 name: None,
                 variable_definitions: None,
                 directives: vec![],
@@ -0,0 +1,161 @@
+use graphql::language::parser::*;
+use graphql::language::lexer::*;
+use graphql::language::ast::*;
+use graphql::language::printer::*;
+
+use std::io::Read;
+use std::fs::File;
+
+fn parse(query: &str) -> Document {
+    Parser::parse(Source::new(query), ParseOptions::no_location()).unwrap()
+}
+
+fn read(path: &str) -> String {
+    let mut contents = String::new();
+    File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+    contents
+}
+
+fn assert_round_trips(query: &str) {
+    let document = parse(query);
+    let printed = print(&document);
+    assert_eq!(document, parse(&printed), "printed as:\n{}", printed);
+    assert_eq!(printed, print(&parse(&printed)));
+}
+
+#[test]
+fn it_prints_the_kitchen_sink() {
+    let document = parse(&read("tests/data/kitchen-sink.graphql"));
+    assert_eq!(print(&document), r#"query queryName($foo: ComplexType, $site: Site = MOBILE) {
+  whoever123is: node(id: [123, 456]) {
+    id
+    ... on User @defer {
+      field2 {
+        id
+        alias: field1(first: 10, after: $foo) @include(if: $foo) {
+          id
+          ...frag
+        }
+      }
+    }
+  }
+}
+
+mutation likeStory {
+  like(story: 123) @defer {
+    story {
+      id
+    }
+  }
+}
+
+fragment frag on Friend {
+  foo(size: $size, bar: $b, obj: {key: "value"})
+}
+
+{
+  unnamed(truthy: true, falsey: false)
+  query
+}"#);
+}
+
+#[test]
+fn it_prints_operations() {
+    assert_eq!("{\n  a\n}", print(&parse("query { a }")));
+    assert_eq!("query Q {\n  a\n}", print(&parse("query Q { a }")));
+    assert_eq!("query @d {\n  a\n}", print(&parse("query @d { a }")));
+    assert_eq!("subscription {\n  a\n}", print(&parse("subscription { a }")));
+    assert_eq!(
+        "query ($a: [Int!]! = [1, 2] @d, $b: In = {c: null, d: ENUM}) {\n  a(a: $a)\n}",
+        print(&parse("query ($a: [Int!]! = [1, 2] @d $b: In = { c: null, d: ENUM }) { a(a: $a) }"))
+    );
+}
+
+#[test]
+fn it_prints_strings() {
+    assert_eq!(r#""a \"b\" \\ \n\t\u0001 é""#, print_value(&Parser::parse_value(
+        Source::new(r#""a \"b\" \\ \n\t\u0001 é""#), ParseOptions::new()).unwrap()));
+    assert_eq!(r#""""a "b" \""" c""""#, print_value(&Parser::parse_value(
+        Source::new(r#""""a "b" \""" c""""#), ParseOptions::new()).unwrap()));
+    assert_eq!("\"\"\"  indented\"\"\"", print_value(&Parser::parse_value(
+        Source::new("\"\"\"\n  first\n    indented\n\"\"\""), ParseOptions::new()).map(|v| match v {
+            Value::StringValue { kind, loc, .. } => Value::StringValue { kind, value: "  indented".to_owned(), block: true, loc },
+            v => v
+        }).unwrap()));
+}
+
+#[test]
+fn it_prints_types() {
+    let t_type = Parser::parse_type(Source::new("[[Int]!]!"), ParseOptions::new()).unwrap();
+    assert_eq!("[[Int]!]!", print_type(&t_type));
+}
+
+#[test]
+fn it_prints_directive_definitions() {
+    assert_eq!(
+        "directive @a(b: Int = 1, c: [String!]) repeatable on FIELD | QUERY",
+        print(&parse("directive @a(b: Int = 1 c: [String!]) repeatable on | FIELD | QUERY"))
+    );
+    assert_eq!(
+        "\"Described\"\ndirective @a(\n  \"\"\"\n  The b\n  argument\n  \"\"\"\n  b: Int\n  c: Int\n) on FIELD",
+        print(&parse("\"Described\" directive @a(\"\"\"\nThe b\nargument\n\"\"\" b: Int, c: Int) on FIELD"))
+    );
+}
+
+#[test]
+fn it_round_trips() {
+    assert_round_trips(&read("tests/data/kitchen-sink.graphql"));
+    assert_round_trips(&read("tests/data/introspection.graphql"));
+    assert_round_trips("query Q($a: Int = 1 @d) @d { a: b(c: [{d: \"e\\u00e9\\n\"}]) @skip(if: false) }");
+    assert_round_trips("fragment F on T @d { ... on U { ...G @d } }");
+    assert_round_trips("{ ... @include(if: $a) { b } ... { c } }");
+    assert_round_trips("{ a(b: \"\"\"\n    multiple\n      lines \\\"\"\"\n\n    with a blank\"\"\") { c(d: \"\"\"  leading\"\"\" e: \"\"\"a \"quote\"\n\"\"\" f: \"\"\"a slash \\\n\"\"\") } }");
+    assert_round_trips("{ a(b: \"\"\"a very long single line block string that goes on and on past seventy chars\"\"\") }");
+    assert_round_trips("\"\"\"\nDescribed\n\"\"\"\ndirective @a(\"b\" b: Int = 1 @d, c: String) repeatable on FIELD | FRAGMENT_SPREAD");
+}
+
+fn assert_compact(query: &str) {
+    let document = parse(query);
+    let compact = print_compact(&document);
+    assert_eq!(document, parse(&compact), "printed as:\n{}", compact);
+    assert_eq!(compact, minify(Source::new(query)).unwrap());
+    assert_eq!(compact, minify(Source::new(&print(&document))).unwrap());
+}
+
+#[test]
+fn it_prints_compactly() {
+    let document = parse(&read("tests/data/kitchen-sink.graphql"));
+    assert_eq!(print_compact(&document), concat!(
+        "query queryName($foo:ComplexType$site:Site=MOBILE){whoever123is:node(id:[123 456]){id",
+        "...on User@defer{field2{id alias:field1(first:10 after:$foo)@include(if:$foo){id...frag}}}}}",
+        "mutation likeStory{like(story:123)@defer{story{id}}}",
+        "fragment frag on Friend{foo(size:$size bar:$b obj:{key:\"value\"})}",
+        "{unnamed(truthy:true falsey:false)query}"
+    ));
+
+    assert_compact(&read("tests/data/kitchen-sink.graphql"));
+    assert_compact(&read("tests/data/introspection.graphql"));
+    assert_compact("{ a(b: [\"\" \"c\" 1.5 ENUM], d: \"\\u00e9\\t\") }");
+    assert_compact("{ a(b: \"\"\"\n    multiple\n      lines \\\"\"\"\n\n    with a blank\n  \"\"\" c: \"\"\"  leading\"\"\") }");
+    assert_compact("\"\"\"\nDescribed\n\"\"\"\ndirective @a(\"b\" b: Int = 1 @d, c: String) repeatable on FIELD | FRAGMENT_SPREAD");
+}
+
+#[test]
+fn it_prints_built_documents_compactly() {
+    let mut document = parse("{ a(b: 1) }");
+    if let Definition::Operation { ref mut selection_set, .. } = document.definitions[0] {
+        if let Selection::Field { ref mut name, ref mut arguments, .. } = selection_set.selections[0] {
+            name.value = "not a name".to_owned();
+            if let Value::IntValue { ref mut value, .. } = arguments[0].value {
+                *value = "1 2".to_owned();
+            }
+        }
+    }
+    assert_eq!("{not a name(b:1 2)}", print_compact(&document));
+}
+
+#[test]
+fn it_minifies_source() {
+    assert_eq!("{a(b:\"c\\\"d\")...F}", minify(Source::new("{\n  # comment\n  a(b: \"c\\u0022d\",),\n  ... F\n}")).unwrap());
+    assert!(minify(Source::new("{ a(b: \"unterminated) }")).is_err());
+}
@@ -0,0 +1,177 @@
+use graphql::language::parser::*;
+use graphql::language::lexer::*;
+use graphql::language::ast::*;
+use graphql::language::kinds::*;
+use graphql::language::visitor::*;
+
+fn parse(query: &str) -> Document {
+    Parser::parse(Source::new(query), ParseOptions::no_location()).unwrap()
+}
+
+struct Recorder {
+    events: Vec<(&'static str, Kinds)>,
+    skip: Option<Kinds>,
+    stop: Option<Kinds>
+}
+
+impl Recorder {
+    fn new() -> Recorder {
+        Recorder { events: vec![], skip: None, stop: None }
+    }
+}
+
+impl<'a> Visitor<'a> for Recorder {
+    fn enter(&mut self, node: Node<'a>) -> VisitAction {
+        self.events.push(("enter", node.kind()));
+        if Some(node.kind()) == self.stop {
+            VisitAction::Break
+        } else if Some(node.kind()) == self.skip {
+            VisitAction::Skip
+        } else {
+            VisitAction::Continue
+        }
+    }
+
+    fn leave(&mut self, node: Node<'a>) -> VisitAction {
+        self.events.push(("leave", node.kind()));
+        VisitAction::Continue
+    }
+}
+
+#[test]
+fn it_visits_nodes_in_order() {
+    let document = parse("{ a(x: $v) }");
+    let mut recorder = Recorder::new();
+    visit(&document, &mut recorder);
+
+    assert_eq!(recorder.events, vec![
+        ("enter", Kinds::Document),
+        ("enter", Kinds::OperationDefinition),
+        ("enter", Kinds::SelectionSet),
+        ("enter", Kinds::Field),
+        ("enter", Kinds::Name),
+        ("leave", Kinds::Name),
+        ("enter", Kinds::Argument),
+        ("enter", Kinds::Name),
+        ("leave", Kinds::Name),
+        ("enter", Kinds::Variable),
+        ("enter", Kinds::Name),
+        ("leave", Kinds::Name),
+        ("leave", Kinds::Variable),
+        ("leave", Kinds::Argument),
+        ("leave", Kinds::Field),
+        ("leave", Kinds::SelectionSet),
+        ("leave", Kinds::OperationDefinition),
+        ("leave", Kinds::Document)
+    ]);
+}
+
+#[test]
+fn it_skips_subtrees() {
+    let document = parse("{ a { b } c }");
+    let mut recorder = Recorder::new();
+    recorder.skip = Some(Kinds::Field);
+    visit(&document, &mut recorder);
+
+    assert_eq!(recorder.events, vec![
+        ("enter", Kinds::Document),
+        ("enter", Kinds::OperationDefinition),
+        ("enter", Kinds::SelectionSet),
+        ("enter", Kinds::Field),
+        ("enter", Kinds::Field),
+        ("leave", Kinds::SelectionSet),
+        ("leave", Kinds::OperationDefinition),
+        ("leave", Kinds::Document)
+    ]);
+}
+
+#[test]
+fn it_stops_early() {
+    let document = parse("{ a(x: 1) b }");
+    let mut recorder = Recorder::new();
+    recorder.stop = Some(Kinds::Argument);
+    visit(&document, &mut recorder);
+
+    assert_eq!(recorder.events.last(), Some(&("enter", Kinds::Argument)));
+    assert_eq!(7, recorder.events.len());
+}
+
+#[test]
+fn it_dispatches_on_kinds() {
+    let document = parse("
+query Q($id: ID = 4) { node(id: $id) { ...F ... on User { name } } }
+fragment F on Node { id }
+");
+    let mut fields = vec![];
+    let mut spreads = 0;
+    let mut inline = 0;
+    {
+        let mut visitor = KindVisitor::new();
+        visitor.on_enter(Kinds::Field, |node| {
+            if let Node::Selection(Selection::Field { name, .. }) = node {
+                fields.push(name.value.clone());
+            }
+            VisitAction::Continue
+        });
+        visitor.on_leave(Kinds::FragmentSpread, |_| { spreads += 1; VisitAction::Continue });
+        visitor.on_leave(Kinds::InlineFragment, |_| { inline += 1; VisitAction::Continue });
+        visit(&document, &mut visitor);
+    }
+
+    assert_eq!(fields, vec!["node", "name", "id"]);
+    assert_eq!((spreads, inline), (1, 1));
+}
+
+#[test]
+fn it_visits_in_parallel() {
+    let document = parse("{ a { b } c }");
+
+    let mut all = Recorder::new();
+    let mut skipping = Recorder::new();
+    skipping.skip = Some(Kinds::SelectionSet);
+    let mut stopping = Recorder::new();
+    stopping.stop = Some(Kinds::Field);
+    {
+        let mut parallel = ParallelVisitor::new(vec![&mut all, &mut skipping, &mut stopping]);
+        visit(&document, &mut parallel);
+    }
+
+    let mut alone = Recorder::new();
+    visit(&document, &mut alone);
+    assert_eq!(all.events, alone.events);
+
+    assert_eq!(skipping.events, vec![
+        ("enter", Kinds::Document),
+        ("enter", Kinds::OperationDefinition),
+        ("enter", Kinds::SelectionSet),
+        ("leave", Kinds::OperationDefinition),
+        ("leave", Kinds::Document)
+    ]);
+
+    assert_eq!(stopping.events, vec![
+        ("enter", Kinds::Document),
+        ("enter", Kinds::OperationDefinition),
+        ("enter", Kinds::SelectionSet),
+        ("enter", Kinds::Field)
+    ]);
+}
+
+#[test]
+fn it_stops_parallel_visits_once_every_visitor_breaks() {
+    let document = parse("{ a { b } c }");
+
+    let mut first = Recorder::new();
+    first.stop = Some(Kinds::SelectionSet);
+    let mut second = Recorder::new();
+    second.stop = Some(Kinds::Field);
+    let mut entered = 0;
+    {
+        let mut counter = KindVisitor::new();
+        counter.on_enter(Kinds::Name, |_| { entered += 1; VisitAction::Break });
+        let mut parallel = ParallelVisitor::new(vec![&mut first, &mut second, &mut counter]);
+        visit(&document, &mut parallel);
+    }
+
+    assert_eq!(entered, 1);
+    assert_eq!(second.events.len(), 4);
+}
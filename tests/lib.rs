@@ -1,4 +1,8 @@
 extern crate graphql;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
+pub mod executor;
 pub mod language;
 pub mod star_wars;
+pub mod value;
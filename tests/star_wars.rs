@@ -3,10 +3,10 @@ extern crate log;
 extern crate env_logger;
 
 use graphql::types::definition::*;
+use graphql::request::Request;
+use graphql::value::{Value, Map};
 use graphql::GraphQL;
 
-use self::env_logger::*;
-
 use std::collections::HashMap;
 
 /*
@@ -46,12 +46,75 @@ use std::collections::HashMap;
  *
  */
 
-fn setup_schema() -> Schema {
+/// The characters by id, handed to resolvers through the request context.
+pub struct StarWarsData {
+    characters: HashMap<String, Value>
+}
+
+impl StarWarsData {
+    fn new() -> StarWarsData {
+        let mut characters = HashMap::new();
+        {
+            let mut add = |id: &str, name: &str, friends: &[&str], appears_in: &[i64], extra: (&str, Option<&str>)| {
+                let mut character = Map::new();
+                character.insert("id".to_owned(), id.into());
+                character.insert("name".to_owned(), name.into());
+                character.insert("friends".to_owned(), friends.to_vec().into());
+                character.insert("appearsIn".to_owned(), appears_in.to_vec().into());
+                character.insert(extra.0.to_owned(), extra.1.into());
+                characters.insert(id.to_owned(), character.into());
+            };
+            add("1000", "Luke Skywalker", &["1002", "1003", "2000", "2001"], &[4, 5, 6], ("homePlanet", Some("Tatooine")));
+            add("1001", "Darth Vader", &["1004"], &[4, 5, 6], ("homePlanet", Some("Tatooine")));
+            add("1002", "Han Solo", &["1000", "1003", "2001"], &[4, 5, 6], ("homePlanet", None));
+            add("1003", "Leia Organa", &["1000", "1002", "2000", "2001"], &[4, 5, 6], ("homePlanet", Some("Alderaan")));
+            add("1004", "Wilhuff Tarkin", &["1001"], &[4], ("homePlanet", None));
+            add("2000", "C-3PO", &["1000", "1002", "1003", "2001"], &[4, 5, 6], ("primaryFunction", Some("Protocol")));
+            add("2001", "R2-D2", &["1000", "1002", "1003"], &[4, 5, 6], ("primaryFunction", Some("Astromech")));
+        }
+        StarWarsData { characters }
+    }
+
+    pub fn character(&self, id: &str) -> Value {
+        self.characters.get(id).cloned().unwrap_or(Value::Null)
+    }
+}
+
+fn character_fields() -> Vec<Field> {
+    let mut friends = Field::new("friends", TypeRef::list(TypeRef::named("Character")));
+    friends.set_resolver(|info| {
+        let data = info.context.get::<StarWarsData>().unwrap();
+        match info.parent.get("friends") {
+            Some(Value::List(ids)) => Ok(Value::List(ids.iter().map(|id| match *id {
+                Value::String(ref id) => data.character(id),
+                _ => Value::Null
+            }).collect())),
+            _ => Ok(Value::Null)
+        }
+    });
+
+    vec![
+        Field::new("id", TypeRef::non_null(TypeRef::named("String"))),
+        Field::new("name", TypeRef::named("String")),
+        friends,
+        Field::new("appearsIn", TypeRef::list(TypeRef::named("Episode")))
+    ]
+}
+
+pub fn setup_schema() -> Schema {
     let mut episode_enum_values = HashMap::new();
     episode_enum_values.insert(String::from("NEWHOPE"), EnumValue {
         value: 4,
         description: String::from("Released in 1977.")
     });
+    episode_enum_values.insert(String::from("EMPIRE"), EnumValue {
+        value: 5,
+        description: String::from("Released in 1980.")
+    });
+    episode_enum_values.insert(String::from("JEDI"), EnumValue {
+        value: 6,
+        description: String::from("Released in 1983.")
+    });
 
     let episode_enum = Enum {
         name: String::from("Episode"),
@@ -59,24 +122,234 @@ fn setup_schema() -> Schema {
         values: episode_enum_values
     };
 
-    Schema {
-        query: Object {
-            name: String::from("Query")
-        }
+    let mut character = Interface::new("Character");
+    character.set_resolve_type(|value, _| match value.get("id") {
+        Some(Value::String(id)) if id.starts_with('1') => Some("Human".to_owned()),
+        Some(Value::String(_)) => Some("Droid".to_owned()),
+        _ => None
+    });
+    let mut human = Object::new("Human");
+    human.add_interface("Character");
+    let mut droid = Object::new("Droid");
+    droid.add_interface("Character");
+    for field in character_fields() {
+        character.add_field(field);
     }
+    for field in character_fields() {
+        human.add_field(field);
+    }
+    human.add_field(Field::new("homePlanet", TypeRef::named("String")));
+    for field in character_fields() {
+        droid.add_field(field);
+    }
+    droid.add_field(Field::new("primaryFunction", TypeRef::named("String")));
+
+    let mut hero = Field::new("hero", TypeRef::named("Character"));
+    hero.add_argument(InputValue::new("episode", TypeRef::named("Episode")));
+    hero.set_resolver(|info| {
+        let data = info.context.get::<StarWarsData>().unwrap();
+        match info.arguments.get::<Option<String>>("episode")?.as_ref().map(|episode| &episode[..]) {
+            Some("EMPIRE") => Ok(data.character("1000")),
+            _ => Ok(data.character("2001"))
+        }
+    });
+
+    let mut human_field = Field::new("human", TypeRef::named("Human"));
+    human_field.add_argument(InputValue::new("id", TypeRef::non_null(TypeRef::named("String"))));
+    human_field.set_resolver(|info| {
+        let id = info.arguments.get::<String>("id")?;
+        Ok(if id.starts_with('1') { info.context.get::<StarWarsData>().unwrap().character(&id) } else { Value::Null })
+    });
+
+    let mut droid_field = Field::new("droid", TypeRef::named("Droid"));
+    droid_field.add_argument(InputValue::new("id", TypeRef::non_null(TypeRef::named("String"))));
+    droid_field.set_resolver(|info| {
+        let id = info.arguments.get::<String>("id")?;
+        Ok(if id.starts_with('2') { info.context.get::<StarWarsData>().unwrap().character(&id) } else { Value::Null })
+    });
+
+    let mut query = Object::new("Query");
+    query.add_field(hero);
+    query.add_field(human_field);
+    query.add_field(droid_field);
+
+    let mut schema = Schema::new(query);
+    schema.add_type(episode_enum);
+    schema.add_type(character);
+    schema.add_type(human);
+    schema.add_type(droid);
+    schema
+}
+
+pub fn run(query: &str) -> String {
+    let mut request = Request::new(query);
+    request.context.set(StarWarsData::new());
+    GraphQL::execute(&setup_schema(), &request).to_json()
 }
 
 #[test]
 fn basic_query() {
-    let schema = setup_schema();
     let query = "
 query HeroNameQuery {
     hero {
         name
     }
 }
-".to_string();
-    let expected = String::from("");
-    let result = GraphQL::query(schema, query);
-    assert_eq!(result, expected);
+";
+    assert_eq!(r#"{"data":{"hero":{"name":"R2-D2"}}}"#, run(query));
+}
+
+#[test]
+fn it_queries_ids_and_friends() {
+    let query = "
+query HeroNameAndFriendsQuery {
+    hero {
+        id
+        name
+        friends {
+            name
+        }
+    }
+}
+";
+    assert_eq!(
+        concat!(r#"{"data":{"hero":{"id":"2001","name":"R2-D2","friends":["#,
+                r#"{"name":"Luke Skywalker"},{"name":"Han Solo"},{"name":"Leia Organa"}]}}}"#),
+        run(query)
+    );
 }
+
+#[test]
+fn it_queries_nested_friends_with_enums_and_fragments() {
+    let query = "
+query NestedQuery {
+    hero {
+        name
+        ...Friends
+    }
+}
+
+fragment Friends on Character {
+    friends {
+        name
+        appearsIn
+        friends { name }
+    }
+}
+";
+    let result = run(query);
+    assert!(result.starts_with(concat!(r#"{"data":{"hero":{"name":"R2-D2","friends":[{"name":"Luke Skywalker","#,
+                                       r#""appearsIn":["NEWHOPE","EMPIRE","JEDI"],"friends":[{"name":"Han Solo"},"#)));
+    assert!(result.ends_with(r#"{"name":"R2-D2"}]}]}}}"#));
+}
+
+#[test]
+fn it_resolves_the_concrete_type_of_characters() {
+    assert_eq!(r#"{"data":{"hero":{"__typename":"Droid","name":"R2-D2"}}}"#, run("{ hero { __typename name } }"));
+    assert_eq!(
+        r#"{"data":{"hero":{"__typename":"Human","name":"Luke Skywalker"}}}"#,
+        run("{ hero(episode: EMPIRE) { __typename name } }")
+    );
+}
+
+#[test]
+fn it_applies_fragments_on_the_concrete_type() {
+    let query = "
+query UseInlineFragments {
+    hero {
+        name
+        ... on Droid { primaryFunction }
+        ...HumanFragment
+    }
+    luke: hero(episode: EMPIRE) {
+        name
+        ... on Droid { primaryFunction }
+        ...HumanFragment
+    }
+}
+
+fragment HumanFragment on Human {
+    homePlanet
+}
+";
+    assert_eq!(
+        concat!(r#"{"data":{"hero":{"name":"R2-D2","primaryFunction":"Astromech"},"#,
+                r#""luke":{"name":"Luke Skywalker","homePlanet":"Tatooine"}}}"#),
+        run(query)
+    );
+    assert_eq!(
+        concat!(r#"{"data":{"hero":{"friends":[{"name":"Luke Skywalker","homePlanet":"Tatooine"},"#,
+                r#"{"name":"Han Solo","homePlanet":null},{"name":"Leia Organa","homePlanet":"Alderaan"}]}}}"#),
+        run("{ hero { friends { name ... on Character { ... on Human { homePlanet } } } } }")
+    );
+}
+
+#[test]
+fn it_aliases_fields() {
+    assert_eq!(r#"{"data":{"droid":{"who":"R2-D2"}}}"#, run("{ droid: hero { who: name } }"));
+}
+
+#[test]
+fn it_queries_with_arguments() {
+    assert_eq!(r#"{"data":{"hero":{"name":"Luke Skywalker"}}}"#, run("{ hero(episode: EMPIRE) { name } }"));
+    assert_eq!(r#"{"data":{"hero":{"name":"R2-D2"}}}"#, run("{ hero(episode: JEDI) { name } }"));
+    assert_eq!(r#"{"data":{"human":{"name":"Luke Skywalker"}}}"#, run(r#"{ human(id: "1000") { name } }"#));
+    assert_eq!(r#"{"data":{"human":null}}"#, run(r#"{ human(id: "not a valid id") { name } }"#));
+    assert_eq!(
+        r#"{"data":{"luke":{"name":"Luke Skywalker"},"leia":{"name":"Leia Organa"}}}"#,
+        run(r#"{ luke: human(id: "1000") { name } leia: human(id: "1003") { name } }"#)
+    );
+}
+
+#[test]
+fn it_queries_with_variables() {
+    let query = "query FetchSomeIDQuery($someId: String!) { human(id: $someId) { name } }";
+    let mut request = Request::new(query);
+    request.context.set(StarWarsData::new());
+    request.variables.insert("someId".to_owned(), Value::String("1002".to_owned()));
+    assert_eq!(r#"{"data":{"human":{"name":"Han Solo"}}}"#, GraphQL::execute(&setup_schema(), &request).to_json());
+
+    request.variables.insert("someId".to_owned(), Value::String("2000".to_owned()));
+    assert_eq!(r#"{"data":{"human":null}}"#, GraphQL::execute(&setup_schema(), &request).to_json());
+}
+
+#[test]
+fn it_reports_request_errors() {
+    assert_eq!(
+        r#"{"errors":[{"message":"Unknown operation named \"Missing\"."}]}"#,
+        {
+            let mut request = Request::new("query A { hero { name } }");
+            request.operation_name = Some("Missing".to_owned());
+            GraphQL::execute(&setup_schema(), &request).to_json()
+        }
+    );
+    assert_eq!(r#"{"errors":[{"message":"Expected Name, found EOF","locations":[{"line":1,"column":9}]}]}"#, run("{ hero {"));
+    assert_eq!(r#"{"errors":[{"message":"Schema is not configured for mutations."}]}"#, run("mutation { hero }"));
+}
+
+#[test]
+fn it_rejects_deeply_nested_queries() {
+    let depth = 100_000;
+    let selections = format!("{{{}{}}}", "hero{".repeat(depth), "}".repeat(depth));
+    assert_eq!(
+        r#"{"errors":[{"message":"Document exceeds the maximum nesting depth of 128","locations":[{"line":1,"column":641}]}]}"#,
+        run(&selections)
+    );
+
+    let list = format!("{{ hero(episode: {}{}) {{ name }} }}", "[".repeat(depth), "]".repeat(depth));
+    assert_eq!(
+        r#"{"errors":[{"message":"Document exceeds the maximum nesting depth of 128","locations":[{"line":1,"column":144}]}]}"#,
+        run(&list)
+    );
+}
+
+#[test]
+fn it_reports_field_errors_with_locations() {
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Cannot query field \"secret\" on type \"Droid\".","#,
+                r#""locations":[{"line":1,"column":15}],"path":["hero","secret"]}],"#,
+                r#""data":{"hero":{"name":"R2-D2","secret":null}}}"#),
+        run("{ hero { name secret } }")
+    );
+}
+
@@ -0,0 +1,202 @@
+extern crate graphql;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+use graphql::value::{Value, Map, FromValue};
+use graphql::value::json::{self, JsonError};
+use graphql::request::{Request, Response};
+use graphql::errors::{GraphQLError, PathSegment};
+use graphql::language::lexer::SourceLocation;
+
+use std::collections::{BTreeMap, HashMap};
+
+#[test]
+fn it_keeps_object_fields_in_insertion_order() {
+    let mut map = Map::new();
+    map.insert("zebra".to_owned(), 1.into());
+    map.insert("apple".to_owned(), 2.into());
+    map.insert("mango".to_owned(), 3.into());
+    assert_eq!(vec!["zebra", "apple", "mango"], map.keys().collect::<Vec<_>>());
+
+    assert_eq!(Some(Value::Int(2)), map.insert("apple".to_owned(), 4.into()));
+    assert_eq!(vec!["zebra", "apple", "mango"], map.keys().collect::<Vec<_>>());
+    assert_eq!(Some(&Value::Int(4)), map.get("apple"));
+
+    assert_eq!(Some(Value::Int(1)), map.remove("zebra"));
+    assert_eq!(None, map.remove("zebra"));
+    assert_eq!(r#"{"apple":4,"mango":3}"#, Value::from(map).to_json());
+}
+
+#[test]
+fn it_keeps_large_objects_in_insertion_order() {
+    let mut map = Map::new();
+    for i in (0..100).rev() {
+        map.insert(format!("k{}", i), i.into());
+    }
+    assert_eq!(Some(Value::Int(50)), map.insert("k50".to_owned(), 0.into()));
+    assert_eq!(Some(Value::Int(99)), map.remove("k99"));
+    assert_eq!(Some(Value::Int(10)), map.remove("k10"));
+    assert_eq!(None, map.get("k10"));
+    assert_eq!(Some(&Value::Int(9)), map.get("k9"));
+    *map.get_mut("k0").unwrap() = 100.into();
+
+    let keys: Vec<_> = (0..99).rev().filter(|&i| i != 10).map(|i| format!("k{}", i)).collect();
+    assert_eq!(keys, map.keys().collect::<Vec<_>>());
+    assert_eq!((Some(&Value::Int(0)), Some(&Value::Int(100))), (map.get("k50"), map.get("k0")));
+
+    let mut small: Map = map.iter().take(3).cloned().collect();
+    assert_ne!(map, small);
+    small.extend(map.iter().skip(3).cloned());
+    assert_eq!(map, small);
+}
+
+#[test]
+fn it_converts_from_rust_values() {
+    assert_eq!(Value::Int(1), 1i32.into());
+    assert_eq!(Value::Float(1.5), 1.5f64.into());
+    assert_eq!(Value::Boolean(true), true.into());
+    assert_eq!(Value::String("a".to_owned()), "a".into());
+    assert_eq!(Value::Null, Option::<i32>::None.into());
+    assert_eq!(Value::Int(1), Some(1).into());
+    assert_eq!(Value::List(vec![Value::Int(1), Value::Null]), vec![Some(1), None].into());
+
+    let mut hash_map = HashMap::new();
+    hash_map.insert("b", 2);
+    hash_map.insert("a", 1);
+    assert_eq!(r#"{"a":1,"b":2}"#, Value::from(hash_map).to_json());
+
+    let mut btree_map = BTreeMap::new();
+    btree_map.insert("y".to_owned(), vec!["x"]);
+    assert_eq!(r#"{"y":["x"]}"#, Value::from(btree_map).to_json());
+}
+
+#[test]
+fn it_converts_into_rust_values() {
+    assert_eq!(Some(3i64), i64::from_value(&Value::Int(3)));
+    assert_eq!(None, i32::from_value(&Value::Int(1 << 40)));
+    assert_eq!(Some(Some("RED".to_owned())), Option::<String>::from_value(&Value::Enum("RED".to_owned())));
+    assert_eq!(Some(None), Option::<String>::from_value(&Value::Null));
+    assert_eq!(None, String::from_value(&Value::Null));
+    assert_eq!(Some(vec![1.0, 2.5]), Vec::<f64>::from_value(&vec![Value::Int(1), Value::Float(2.5)].into()));
+}
+
+#[test]
+fn it_writes_json() {
+    let value: Value = vec![Value::String("a\"b\n\u{1}\u{8}é".to_owned()), Value::Float(f64::NAN), Value::Enum("E".to_owned())].into();
+    assert_eq!(r#"["a\"b\n\u0001\bé",null,"E"]"#, value.to_json());
+
+    let floats: Value = vec![1.0, 0.1, -2.5, 1e300, 1.5e-9].into();
+    assert_eq!("[1.0,0.1,-2.5,1e300,1.5e-9]", floats.to_json());
+}
+
+#[test]
+fn it_reads_json() {
+    let input = r#" {"b": [1, -2, 3.5, 1e2, 12345678901234567890], "a": {"x": null, "y": true, "z": false}, "s": "q\"\\\/\n\u00e9\ud83d\ude00"} "#;
+    let value = Value::from_json(input).unwrap();
+    assert_eq!(vec!["b", "a", "s"], match value { Value::Object(ref fields) => fields.keys().collect::<Vec<_>>(), _ => vec![] });
+    assert_eq!(
+        r#"{"b":[1,-2,3.5,100.0,1.2345678901234567e19],"a":{"x":null,"y":true,"z":false},"s":"q\"\\/\né😀"}"#,
+        value.to_json()
+    );
+    assert_eq!(value, Value::from_json(&value.to_json()).unwrap());
+    assert_eq!(Value::Object(Map::new()), Value::from_json("{}").unwrap());
+    assert_eq!(Value::List(vec![]), Value::from_json(" [ ] ").unwrap());
+}
+
+#[test]
+fn it_reports_invalid_json() {
+    let error = |description: &str, position| Err(JsonError { description: description.to_owned(), position });
+    assert_eq!(error("Unexpected end of input", 0), Value::from_json(""));
+    assert_eq!(error("Expected , or ]", 3), Value::from_json("[1 2]"));
+    assert_eq!(error("Expected a string key", 1), Value::from_json("{a: 1}"));
+    assert_eq!(error("Unexpected trailing characters", 5), Value::from_json("true false"));
+    assert_eq!(error("Expected a digit", 1), Value::from_json("-"));
+    assert_eq!(error("Expected a digit", 2), Value::from_json("1."));
+    assert_eq!(error("Unterminated string", 4), Value::from_json(r#""abc"#));
+    assert_eq!(error("Unpaired surrogate", 7), Value::from_json(r#""\ud83d""#));
+    assert_eq!(error("Nested too deeply", 256), Value::from_json(&"[".repeat(300)));
+}
+
+#[test]
+fn it_streams_responses() {
+    let mut response = Response::from_error(GraphQLError::new("Oops".to_owned()));
+    response.data = Some(vec![1, 2].into());
+    let mut out = vec![];
+    response.write_json(&mut out).unwrap();
+    assert_eq!(r#"{"errors":[{"message":"Oops"}],"data":[1,2]}"#, String::from_utf8(out).unwrap());
+    assert_eq!(response.to_json(), json::to_string(&response.to_value()));
+
+    let mut error = GraphQLError::new("Say \"what\"".to_owned());
+    error.locations.push(SourceLocation { line: 2, column: 5 });
+    error.path = vec![PathSegment::Key("a".to_owned()), PathSegment::Index(0)];
+    response.errors.push(error);
+    response.extensions = Some(Value::Object(Map::new()));
+    assert_eq!(
+        concat!(r#"{"errors":[{"message":"Oops"},{"message":"Say \"what\"","locations":[{"line":2,"column":5}],"path":["a",0]}],"#,
+                r#""data":[1,2],"extensions":{}}"#),
+        response.to_json()
+    );
+    assert_eq!(response.to_json(), json::to_string(&response.to_value()));
+}
+
+#[test]
+fn it_reads_requests() {
+    let request = Request::from_json(r#"{"query": "query Q($a: Int) { a }", "operationName": "Q", "variables": {"a": 1}}"#).unwrap();
+    assert_eq!("query Q($a: Int) { a }", request.query);
+    assert_eq!(Some("Q".to_owned()), request.operation_name);
+    assert_eq!(Some(&Value::Int(1)), request.variables.get("a"));
+
+    let request = Request::from_json(r#"{"query": "{ a }", "operationName": null}"#).unwrap();
+    assert_eq!(None, request.operation_name);
+    assert!(request.variables.is_empty());
+
+    assert_eq!(
+        "Expected \"variables\" to be an object at position 32",
+        Request::from_json(r#"{"query": "{ a }", "variables": []}"#).err().unwrap().to_string()
+    );
+    assert_eq!(
+        "Expected \"query\" to be a string at position 10",
+        Request::from_json(r#"{"query": 1}"#).err().unwrap().to_string()
+    );
+    assert_eq!(
+        "Expected a JSON object at position 1",
+        Request::from_json(" []").err().unwrap().to_string()
+    );
+    assert_eq!(
+        "Unexpected trailing characters at position 19",
+        Request::from_json(r#"{"query": "{ a }" }x"#).err().unwrap().to_string()
+    );
+}
+
+#[test]
+fn it_reads_large_objects() {
+    let count = 100_000;
+    let variables: Vec<_> = (0..count).map(|i| format!(r#""v{}": {}"#, i, i)).collect();
+    let body = format!(r#"{{"query": "{{ a }}", "variables": {{{}}}}}"#, variables.join(", "));
+    let request = Request::from_json(&body).unwrap();
+    assert_eq!(count, request.variables.len());
+    assert_eq!(Some(&Value::Int(12_345)), request.variables.get("v12345"));
+
+    let object = Value::from_json(&format!("{{{}}}", variables.join(", "))).unwrap();
+    match object {
+        Value::Object(ref fields) => {
+            assert_eq!(count, fields.len());
+            assert_eq!(Some("v99999"), fields.keys().last());
+        },
+        _ => panic!("expected an object")
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn it_converts_to_and_from_serde_json() {
+    let value = Value::from_json(r#"{"b": [1, 2.5, "x"], "a": null}"#).unwrap();
+    assert_eq!(r#"{"b":[1,2.5,"x"],"a":null}"#, serde_json::to_string(&value).unwrap());
+    assert_eq!(value, serde_json::from_str::<Value>(r#"{"b": [1, 2.5, "x"], "a": null}"#).unwrap());
+
+    let json: serde_json::Value = Value::Enum("RED".to_owned()).into();
+    assert_eq!(serde_json::Value::String("RED".to_owned()), json);
+
+    let response = Response::from_error(GraphQLError::new("Oops".to_owned()));
+    assert_eq!(response.to_json(), serde_json::to_string(&response).unwrap());
+}